@@ -0,0 +1,158 @@
+use crate::joltage::JoltageRules;
+use num_bigint::BigUint;
+
+// ways[i] is the number of chains from adaptors[i] to the last adaptor. The
+// adaptors must come from `JoltageRules::with_ends`.
+fn ways_to_end(adaptors: &[i64], rules: &JoltageRules) -> Vec<BigUint> {
+    let mut ways = vec![BigUint::default(); adaptors.len()];
+    if let Some(last) = ways.last_mut() {
+        *last = BigUint::from(1u32);
+    }
+    for i in (0..adaptors.len().saturating_sub(1)).rev() {
+        let mut total = BigUint::default();
+        for j in (i + 1)..adaptors.len() {
+            let difference = adaptors[j] - adaptors[i];
+            if difference > rules.max_difference() {
+                break;
+            }
+            if rules.allows(difference) {
+                total += &ways[j];
+            }
+        }
+        ways[i] = total;
+    }
+    ways
+}
+
+pub fn count_chains(adaptors: &[i64], rules: &JoltageRules) -> BigUint {
+    ways_to_end(adaptors, rules).into_iter().next().unwrap_or_default()
+}
+
+// Lazily walks every valid chain in lexicographic order of adaptor indices.
+// Adaptors that can't reach the device are never stepped on, so each call to
+// `next` does at most one chain's worth of work.
+pub struct Chains<'a> {
+    adaptors: &'a [i64],
+    rules: &'a JoltageRules,
+    ways: Vec<BigUint>,
+    // Indices of the adaptors in the current chain.
+    stack: Vec<usize>,
+    started: bool,
+}
+
+impl<'a> Chains<'a> {
+    pub fn new(adaptors: &'a [i64], rules: &'a JoltageRules) -> Self {
+        Chains {
+            ways: ways_to_end(adaptors, rules),
+            adaptors,
+            rules,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    // The first adaptor after `from` that can still reach the device, searching
+    // from index `candidate` onwards.
+    fn next_step(&self, from: usize, candidate: usize) -> Option<usize> {
+        (candidate..self.adaptors.len())
+            .take_while(|&j| self.adaptors[j] - self.adaptors[from] <= self.rules.max_difference())
+            .filter(|&j| self.rules.allows(self.adaptors[j] - self.adaptors[from]))
+            .find(|&j| self.ways[j] != BigUint::default())
+    }
+
+    // Extends the chain along first choices until it reaches the device.
+    fn descend(&mut self) {
+        while let Some(&last) = self.stack.last() {
+            if last + 1 == self.adaptors.len() {
+                return;
+            }
+            let next = self.next_step(last, last + 1).unwrap();
+            self.stack.push(next);
+        }
+    }
+}
+
+impl<'a> Iterator for Chains<'a> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Vec<i64>> {
+        if !self.started {
+            self.started = true;
+            match self.ways.first() {
+                Some(ways) if *ways != BigUint::default() => (),
+                _ => return None,
+            }
+            self.stack.push(0);
+        } else {
+            // Backtrack to the deepest adaptor that has another choice.
+            loop {
+                let current = self.stack.pop()?;
+                let previous = *self.stack.last()?;
+                if let Some(next) = self.next_step(previous, current + 1) {
+                    self.stack.push(next);
+                    break;
+                }
+            }
+        }
+        self.descend();
+        Some(self.stack.iter().map(|&i| self.adaptors[i]).collect())
+    }
+}
+
+mod test {
+    #[test]
+    fn example_counts() {
+        use super::count_chains;
+        use crate::joltage::JoltageRules;
+        use num_bigint::BigUint;
+        let rules = JoltageRules::default();
+        let small = rules.with_ends(vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]);
+        assert_eq!(count_chains(&small, &rules), BigUint::from(8u32));
+        let large = rules.with_ends(vec![
+            28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35,
+            8, 17, 7, 9, 4, 2, 34, 10, 3,
+        ]);
+        assert_eq!(count_chains(&large, &rules), BigUint::from(19208u32));
+    }
+
+    #[test]
+    fn long_chain_overflows_i64() {
+        use super::count_chains;
+        use crate::joltage::JoltageRules;
+        // Every adaptor one jolt apart gives tribonacci many chains.
+        let rules = JoltageRules::default();
+        let adaptors = rules.with_ends((1..=200).collect());
+        assert!(count_chains(&adaptors, &rules) > num_bigint::BigUint::from(u64::MAX));
+    }
+
+    #[test]
+    fn enumerate_chains() {
+        use super::Chains;
+        use crate::joltage::JoltageRules;
+        let rules = JoltageRules::default();
+        let small = rules.with_ends(vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]);
+        let chains: Vec<Vec<i64>> = Chains::new(&small, &rules).collect();
+        assert_eq!(chains.len(), 8);
+        assert_eq!(chains[0], vec![0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19, 22]);
+        assert_eq!(chains[7], vec![0, 1, 4, 7, 10, 12, 15, 16, 19, 22]);
+        let large = rules.with_ends((1..=200).collect());
+        assert_eq!(Chains::new(&large, &rules).take(3).count(), 3);
+        assert_eq!(Chains::new(&[0, 4], &rules).next(), None);
+    }
+
+    #[test]
+    fn gaps_outside_the_rules() {
+        use super::{count_chains, Chains};
+        use crate::joltage::JoltageRules;
+        use num_bigint::BigUint;
+        // Only odd differences, so 1 -> 3 is not a step.
+        let rules = JoltageRules::new(vec![1, 3], 0, 3);
+        let adaptors = rules.with_ends(vec![1, 2, 3, 4]);
+        let chains: Vec<Vec<i64>> = Chains::new(&adaptors, &rules).collect();
+        assert_eq!(count_chains(&adaptors, &rules), BigUint::from(chains.len()));
+        assert_eq!(
+            chains,
+            vec![vec![0, 1, 2, 3, 4, 7], vec![0, 1, 4, 7], vec![0, 3, 4, 7]]
+        );
+    }
+}
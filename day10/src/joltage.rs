@@ -0,0 +1,139 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+// Which joltage differences an adapter accepts, what the outlet is rated at
+// and how far above the highest adapter the device sits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoltageRules {
+    allowed: Vec<i64>,
+    pub outlet: i64,
+    pub device_offset: i64,
+}
+
+impl Default for JoltageRules {
+    fn default() -> Self {
+        JoltageRules {
+            allowed: vec![1, 2, 3],
+            outlet: 0,
+            device_offset: 3,
+        }
+    }
+}
+
+impl JoltageRules {
+    pub fn new(mut allowed: Vec<i64>, outlet: i64, device_offset: i64) -> Self {
+        allowed.sort_unstable();
+        allowed.dedup();
+        JoltageRules {
+            allowed,
+            outlet,
+            device_offset,
+        }
+    }
+
+    pub fn allows(&self, difference: i64) -> bool {
+        self.allowed.binary_search(&difference).is_ok()
+    }
+
+    // No allowed difference is larger than this, so chains can stop looking.
+    pub fn max_difference(&self) -> i64 {
+        self.allowed.last().copied().unwrap_or(i64::MIN)
+    }
+
+    pub fn allowed(&self) -> &[i64] {
+        &self.allowed
+    }
+
+    // Sorts the adapters and adds the outlet to the front and the device to the end.
+    pub fn with_ends(&self, mut adaptors: Vec<i64>) -> Vec<i64> {
+        adaptors.sort_unstable();
+        let device = adaptors.last().copied().unwrap_or(self.outlet) + self.device_offset;
+        adaptors.insert(0, self.outlet);
+        adaptors.push(device);
+        adaptors
+    }
+}
+
+// Two neighbouring joltages in the full chain that no rule allows, `index` is
+// the position of `from` in the chain including the outlet.
+#[derive(Debug, PartialEq)]
+pub struct GapError {
+    pub index: usize,
+    pub from: i64,
+    pub to: i64,
+    pub allowed: Vec<i64>,
+}
+
+impl Display for GapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let allowed: Vec<String> = self.allowed.iter().map(|d| d.to_string()).collect();
+        write!(
+            f,
+            "No adapter chain uses every adapter: gap of {} jolts from {} to {} at position {} (allowed differences: {})",
+            self.to - self.from,
+            self.from,
+            self.to,
+            self.index,
+            allowed.join(", ")
+        )
+    }
+}
+
+impl Error for GapError {}
+
+// Counts each difference between neighbouring joltages in a chain built with
+// `JoltageRules::with_ends`.
+pub fn difference_histogram(
+    chain: &[i64],
+    rules: &JoltageRules,
+) -> Result<BTreeMap<i64, usize>, GapError> {
+    let mut histogram = BTreeMap::new();
+    for (index, pair) in chain.windows(2).enumerate() {
+        let difference = pair[1] - pair[0];
+        if !rules.allows(difference) {
+            return Err(GapError {
+                index,
+                from: pair[0],
+                to: pair[1],
+                allowed: rules.allowed().to_vec(),
+            });
+        }
+        *histogram.entry(difference).or_insert(0) += 1;
+    }
+    Ok(histogram)
+}
+
+mod test {
+    #[test]
+    fn example_histogram() {
+        use super::{difference_histogram, JoltageRules};
+        let rules = JoltageRules::default();
+        let chain = rules.with_ends(vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4]);
+        let histogram = difference_histogram(&chain, &rules).unwrap();
+        assert_eq!(histogram.into_iter().collect::<Vec<(i64, usize)>>(), vec![(1, 7), (3, 5)]);
+    }
+
+    #[test]
+    fn custom_rules() {
+        use super::{difference_histogram, GapError, JoltageRules};
+        let rules = JoltageRules::new(vec![5, 1, 4], 10, 4);
+        let chain = rules.with_ends(vec![15, 11, 20]);
+        assert_eq!(chain, vec![10, 11, 15, 20, 24]);
+        let histogram = difference_histogram(&chain, &rules).unwrap();
+        assert_eq!(histogram.into_iter().collect::<Vec<(i64, usize)>>(), vec![(1, 1), (4, 2), (5, 1)]);
+
+        let chain = JoltageRules::default().with_ends(vec![1, 2, 6]);
+        assert_eq!(
+            difference_histogram(&chain, &JoltageRules::default()),
+            Err(GapError {
+                index: 2,
+                from: 2,
+                to: 6,
+                allowed: vec![1, 2, 3]
+            })
+        );
+    }
+}
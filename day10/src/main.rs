@@ -1,62 +1,75 @@
 #![allow(dead_code)]
-use std::{collections::HashMap, env, fs::File, io::{self, BufRead}, path::Path};
+use std::{env, error::Error, fs::File, io::{self, BufRead}, path::Path};
 
-fn main() -> Result<(), io::Error> {
+mod chains;
+mod joltage;
+
+use joltage::JoltageRules;
+
+// Reads --allowed 1,2,3 --outlet 0 --device-offset 3 from the arguments after
+// the subcommand, anything not given keeps the puzzle's value.
+fn parse_rules(args: &[String]) -> Result<JoltageRules, Box<dyn Error>> {
+    let defaults = JoltageRules::default();
+    let mut allowed = defaults.allowed().to_vec();
+    let mut outlet = defaults.outlet;
+    let mut device_offset = defaults.device_offset;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--allowed" => {
+                allowed = value.split(',').map(|d| d.parse::<i64>()).collect::<Result<Vec<i64>, _>>()?;
+            }
+            "--outlet" => outlet = value.parse()?,
+            "--device-offset" => device_offset = value.parse()?,
+            _ => return Err(format!("unknown option {}", flag).into()),
+        }
+    }
+    Ok(JoltageRules::new(allowed, outlet, device_offset))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
-    let mut adaptors: Vec<i64> = io::BufReader::new(input_file).lines().map(|l| l.unwrap().parse::<i64>().unwrap()).collect();
-    adaptors.sort();
-    adaptors.insert(0,0);
-    adaptors.push(adaptors.last().unwrap() + 3);
+    let adaptors: Vec<i64> = io::BufReader::new(input_file).lines().map(|l| l.unwrap().parse::<i64>().unwrap()).collect();
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        if let Some(( low, _, high )) = part1(&adaptors) {
-            println!("Product of low and high joltage differences {}", low * high);
+    if args.first().map(|a| a.as_str()) == Some("part1") {
+        let rules = parse_rules(&args[1..])?;
+        let chain = rules.with_ends(adaptors);
+        match joltage::difference_histogram(&chain, &rules) {
+            Ok(histogram) => {
+                for (difference, count) in histogram.iter() {
+                    println!("{} jolt differences {}", difference, count);
+                }
+                let low = histogram.get(&1).copied().unwrap_or(0);
+                let high = histogram.get(&3).copied().unwrap_or(0);
+                println!("Product of low and high joltage differences {}", low * high);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
         std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        let mut memo: HashMap<i64, i64> = HashMap::new();
-        let permutations = part2(&adaptors, &mut memo);
-        println!("permutations {}", permutations);
+    } else if args.first().map(|a| a.as_str()) == Some("part2") {
+        let rules = parse_rules(&args[1..])?;
+        let chain = rules.with_ends(adaptors);
+        println!("permutations {}", chains::count_chains(&chain, &rules));
+        std::process::exit(0);
+    } else if args.first().map(|a| a.as_str()) == Some("chains") {
+        let (limit, flags) = match args.get(1).and_then(|l| l.parse::<usize>().ok()) {
+            Some(limit) => (limit, &args[2..]),
+            None => (10, &args[1..]),
+        };
+        let rules = parse_rules(flags)?;
+        let chain = rules.with_ends(adaptors);
+        for chain in chains::Chains::new(&chain, &rules).take(limit) {
+            println!("{}", chain.iter().map(|a| a.to_string()).collect::<Vec<String>>().join(" "));
+        }
         std::process::exit(0);
     } else {
-        println!("usage: cargo run (part1 | part2)");
+        println!("usage: cargo run (part1 | part2 | chains [limit]) [--allowed 1,2,3] [--outlet 0] [--device-offset 3]");
         std::process::exit(1);
     }
 }
-
-// Returns none if not sorted or larger than 3 jolt gap
-fn part1(adaptors: &Vec<i64>) -> Option<(i64, i64, i64)> {
-    let mut one_jolt_diff = 0;
-    let mut two_jolt_diff = 0;
-    let mut three_jolt_diff = 0;
-    for pair in adaptors.windows(2) {
-        if pair[1] <= pair[0] {
-            return None;
-        }
-        match pair[1] - pair[0] {
-            1 => one_jolt_diff += 1,
-            2 => two_jolt_diff += 1,
-            3 => three_jolt_diff += 1,
-            _ => return None,
-        }
-    }
-    Some((one_jolt_diff, two_jolt_diff, three_jolt_diff))
-}
-
-fn part2(adaptors: &[i64], memo: &mut HashMap<i64,i64>) -> i64 {
-    if adaptors.len() <= 2 {
-        return 1
-    }
-    if let Some(permutations) = memo.get(&adaptors[0]) {
-        return *permutations;
-    }
-    let mut permutations = 0;
-    for (index, adaptor) in adaptors[1..].iter().take(3).enumerate() {
-        if adaptor - adaptors[0] <= 3 {
-            permutations += part2(&adaptors[index+1..], memo);
-        }
-    }
-    memo.insert(adaptors[0], permutations);
-    permutations
-}
@@ -0,0 +1,135 @@
+use crate::cycle::{CycleDetector, Outcome, StepLimitError};
+use crate::floor_map::{FloorMap, Space};
+
+// Runs a floor map forward one generation at a time. Every seat looks at the
+// positions chosen by `neighbourhood` and `transition` decides what it becomes,
+// all seats update at once. Floor never changes.
+pub struct Automaton<N, T>
+where
+    N: Fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>,
+    T: Fn(Space, &[Space]) -> Space,
+{
+    neighbourhood: N,
+    transition: T,
+}
+
+impl<N, T> Automaton<N, T>
+where
+    N: Fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>,
+    T: Fn(Space, &[Space]) -> Space,
+{
+    pub fn new(neighbourhood: N, transition: T) -> Self {
+        Automaton {
+            neighbourhood,
+            transition,
+        }
+    }
+
+    // The positions the seat at (x, y) looks at.
+    pub fn neighbours(&self, floor_map: &FloorMap, x: usize, y: usize) -> Vec<(usize, usize)> {
+        (self.neighbourhood)(floor_map, x, y)
+    }
+
+    // What `seat` becomes given the spaces at its neighbours' positions.
+    pub fn transition(&self, seat: Space, neighbours: &[Space]) -> Space {
+        (self.transition)(seat, neighbours)
+    }
+
+    // Returns the number of seats that changed.
+    pub fn step(&self, floor_map: &mut FloorMap) -> usize {
+        let mut updates: Vec<(usize, usize, Space)> = Vec::new();
+        for (seat, x, y) in floor_map.seats_with_indices() {
+            let neighbours: Vec<Space> = self
+                .neighbours(floor_map, x, y)
+                .into_iter()
+                .map(|(x, y)| *floor_map.get(x, y).unwrap())
+                .collect();
+            let next = self.transition(seat, &neighbours);
+            if next != seat {
+                updates.push((x, y, next));
+            }
+        }
+        for &(x, y, next) in updates.iter() {
+            floor_map.set(x, y, next).unwrap();
+        }
+        updates.len()
+    }
+
+    // Steps until the layout settles or starts repeating, giving up with an
+    // error after `max_steps` generations.
+    pub fn run(
+        &self,
+        floor_map: &mut FloorMap,
+        max_steps: Option<usize>,
+    ) -> Result<Outcome, StepLimitError> {
+        let mut detector = CycleDetector::new(max_steps);
+        loop {
+            if let Some(outcome) = detector.observe(&floor_map.spaces())? {
+                return Ok(outcome);
+            }
+            self.step(floor_map);
+        }
+    }
+}
+
+// The puzzle's rule: an empty seat with no occupied neighbours fills up and an
+// occupied seat with at least `threshold` occupied neighbours empties.
+pub fn seating_rule(threshold: usize) -> impl Fn(Space, &[Space]) -> Space {
+    move |seat, neighbours| {
+        let occupied = neighbours.iter().filter(|&&s| s == Space::Occupied).count();
+        match seat {
+            Space::Empty if occupied == 0 => Space::Occupied,
+            Space::Occupied if occupied >= threshold => Space::Empty,
+            _ => seat,
+        }
+    }
+}
+
+pub fn adjacent(floor_map: &FloorMap, x: usize, y: usize) -> Vec<(usize, usize)> {
+    floor_map.neighbour_positions(x, y)
+}
+
+pub fn line_of_sight(floor_map: &FloorMap, x: usize, y: usize) -> Vec<(usize, usize)> {
+    floor_map.line_of_sight_positions(x, y)
+}
+
+mod test {
+    #[test]
+    fn example() {
+        use super::{adjacent, line_of_sight, seating_rule, Automaton};
+        use crate::cycle::Outcome;
+        use crate::floor_map::FloorMap;
+        let input: &[u8] = include_bytes!("../test_input.txt");
+        let mut floor_map = FloorMap::from_reader(input).unwrap();
+        let outcome = Automaton::new(adjacent, seating_rule(4)).run(&mut floor_map, None);
+        assert_eq!(outcome, Ok(Outcome::Stable(5)));
+        assert_eq!(floor_map.occupied_count(), 37);
+        let mut floor_map = FloorMap::from_reader(input).unwrap();
+        let outcome = Automaton::new(line_of_sight, seating_rule(5)).run(&mut floor_map, None);
+        assert_eq!(outcome, Ok(Outcome::Stable(6)));
+        assert_eq!(floor_map.occupied_count(), 26);
+    }
+
+    #[test]
+    fn oscillation() {
+        use super::{adjacent, seating_rule, Automaton};
+        use crate::cycle::{Outcome, StepLimitError};
+        use crate::floor_map::FloorMap;
+        let input: &[u8] = include_bytes!("../test_input.txt");
+        // With a threshold of 0 every occupied seat empties again straight away.
+        let automaton = Automaton::new(adjacent, seating_rule(0));
+        let mut floor_map = FloorMap::from_reader(input).unwrap();
+        assert_eq!(
+            automaton.run(&mut floor_map, None),
+            Ok(Outcome::Cycle {
+                first_repeated: 0,
+                period: 2
+            })
+        );
+        let mut floor_map = FloorMap::from_reader(input).unwrap();
+        assert_eq!(
+            automaton.run(&mut floor_map, Some(1)),
+            Err(StepLimitError { max_steps: 1 })
+        );
+    }
+}
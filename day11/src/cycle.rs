@@ -0,0 +1,123 @@
+use crate::floor_map::Space;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    // Nothing changes after this many generations.
+    Stable(usize),
+    // The layout at generation `first_repeated` comes back every `period`
+    // generations, forever.
+    Cycle {
+        first_repeated: usize,
+        period: usize,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StepLimitError {
+    pub max_steps: usize,
+}
+
+impl Display for StepLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seating neither settled nor repeated within {} generations, raise --max-steps to keep going",
+            self.max_steps
+        )
+    }
+}
+
+impl Error for StepLimitError {}
+
+// Remembers a hash of every generation seen so far so the first repeat can be
+// spotted. Only hashes are kept, a 64 bit collision between two different
+// layouts is unlikely enough to ignore.
+pub struct CycleDetector {
+    seen: HashMap<u64, usize>,
+    generation: usize,
+    max_steps: Option<usize>,
+}
+
+impl CycleDetector {
+    // `max_steps` bounds how many generations may follow the initial layout.
+    pub fn new(max_steps: Option<usize>) -> Self {
+        CycleDetector {
+            seen: HashMap::new(),
+            generation: 0,
+            max_steps,
+        }
+    }
+
+    // Feed every generation in order starting with the initial layout. Returns
+    // the outcome once a layout repeats, a layout repeating the one straight
+    // before it is stable.
+    pub fn observe(&mut self, spaces: &[Space]) -> Result<Option<Outcome>, StepLimitError> {
+        let mut hasher = DefaultHasher::new();
+        spaces.hash(&mut hasher);
+        let generation = self.generation;
+        if let Some(&first_repeated) = self.seen.get(&hasher.finish()) {
+            let period = generation - first_repeated;
+            return Ok(Some(if period == 1 {
+                Outcome::Stable(first_repeated)
+            } else {
+                Outcome::Cycle {
+                    first_repeated,
+                    period,
+                }
+            }));
+        }
+        if let Some(max_steps) = self.max_steps {
+            if generation >= max_steps {
+                return Err(StepLimitError { max_steps });
+            }
+        }
+        self.seen.insert(hasher.finish(), generation);
+        self.generation += 1;
+        Ok(None)
+    }
+}
+
+mod test {
+    #[test]
+    fn detects_cycles_and_limits() {
+        use super::{CycleDetector, Outcome, StepLimitError};
+        use crate::floor_map::Space::{Empty, Occupied};
+        let layouts = [
+            vec![Empty, Empty],
+            vec![Occupied, Empty],
+            vec![Occupied, Occupied],
+            vec![Empty, Occupied],
+            vec![Occupied, Occupied],
+        ];
+        let mut detector = CycleDetector::new(None);
+        let outcomes: Vec<_> = layouts
+            .iter()
+            .map(|layout| detector.observe(layout).unwrap())
+            .collect();
+        assert_eq!(outcomes[..4], [None, None, None, None]);
+        assert_eq!(
+            outcomes[4],
+            Some(Outcome::Cycle {
+                first_repeated: 2,
+                period: 2
+            })
+        );
+
+        let mut detector = CycleDetector::new(None);
+        assert_eq!(detector.observe(&layouts[0]), Ok(None));
+        assert_eq!(detector.observe(&layouts[0]), Ok(Some(Outcome::Stable(0))));
+
+        let mut detector = CycleDetector::new(Some(1));
+        assert_eq!(detector.observe(&layouts[0]), Ok(None));
+        assert_eq!(
+            detector.observe(&layouts[1]),
+            Err(StepLimitError { max_steps: 1 })
+        );
+    }
+}
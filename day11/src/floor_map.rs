@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::BufRead,
+};
+
+#[derive(Debug)]
+pub struct FloorMapError;
+impl Display for FloorMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FloorMapError occurred")
+    }
+}
+
+impl Error for FloorMapError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Space {
+    Floor,
+    Empty,
+    Occupied,
+}
+
+impl Space {
+    pub fn symbol(&self) -> char {
+        match self {
+            Space::Floor => '.',
+            Space::Empty => 'L',
+            Space::Occupied => '#',
+        }
+    }
+
+    // The symbol wrapped in ANSI escapes: grey floor, green empty seats and
+    // bold red occupied ones.
+    pub fn paint(&self) -> String {
+        let style = match self {
+            Space::Floor => "38;5;240",
+            Space::Empty => "32",
+            Space::Occupied => "1;31",
+        };
+        format!("\x1b[{}m{}\x1b[0m", style, self.symbol())
+    }
+}
+
+#[derive(Clone)]
+pub struct FloorMap {
+    grid: Vec<Vec<Space>>,
+    width: usize,
+    height: usize,
+}
+
+impl FloorMap {
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let grid: Vec<Vec<Space>> = reader
+            .lines()
+            .map(|l| {
+                l.unwrap()
+                    .chars()
+                    .map(|c| match c {
+                        '.' => Space::Floor,
+                        'L' => Space::Empty,
+                        '#' => Space::Occupied,
+                        _ => std::process::exit(1),
+                    })
+                    .collect::<Vec<Space>>()
+            })
+            .collect();
+        let width = grid[0].len();
+        let height = grid.len();
+        for row in grid.iter() {
+            if row.len() != width {
+                return Err(FloorMapError).context("Not all lines of input were the same length");
+            }
+        }
+        Ok(FloorMap {
+            grid,
+            height,
+            width,
+        })
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Result<&Space> {
+        if x >= self.width || y >= self.height {
+            return Err(FloorMapError).context(format!(
+                "Attempted to get an out of bounds location: ({}, {})",
+                x, y
+            ));
+        }
+        Ok(&self.grid[y][x])
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, val: Space) -> Result<()> {
+        if x >= self.width || y >= self.height {
+            return Err(FloorMapError).context(format!(
+                "Attempted to get an out of bounds location: ({}, {})",
+                x, y
+            ));
+        }
+        self.grid[y][x] = val;
+        Ok(())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Positions of the seats next to (x, y), diagonals included.
+    pub fn neighbour_positions(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for x_offset in -1..2 {
+            for y_offset in -1..2 {
+                if x_offset == 0 && y_offset == 0 {
+                    continue;
+                }
+                let (new_x, new_y) = (
+                    (x as i32 + x_offset) as usize,
+                    (y as i32 + y_offset) as usize,
+                );
+                if let Ok(Space::Occupied) | Ok(Space::Empty) = self.get(new_x, new_y) {
+                    positions.push((new_x, new_y));
+                }
+            }
+        }
+        positions
+    }
+
+    // Positions of the first seat in each of the eight directions from (x, y),
+    // seats never move so these stay the same between generations.
+    pub fn line_of_sight_positions(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut positions: Vec<(usize, usize)> = Vec::new();
+        for (x_offset, y_offset) in [
+            (-1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+            (1, 0),
+            (1, -1),
+            (0, -1),
+            (-1, -1),
+        ]
+        .iter()
+        {
+            let mut new_x = x as i32;
+            let mut new_y = y as i32;
+            loop {
+                new_x += x_offset;
+                new_y += y_offset;
+                match self.get(new_x as usize, new_y as usize) {
+                    Ok(Space::Occupied) | Ok(Space::Empty) => {
+                        positions.push((new_x as usize, new_y as usize));
+                        break;
+                    }
+                    Err(_) => break,
+                    _ => continue,
+                }
+            }
+        }
+        positions
+    }
+
+    pub fn print_map(&self, coloured: bool) {
+        for row in self.grid.iter() {
+            for space in row.iter() {
+                if coloured {
+                    print!("{}", space.paint());
+                } else {
+                    print!("{}", space.symbol());
+                }
+            }
+            println!();
+        }
+    }
+
+    pub fn seats_with_indices(&self) -> Vec<(Space, usize, usize)> {
+        let mut seats: Vec<(Space, usize, usize)> = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                match self.grid[y][x] {
+                    space @ Space::Occupied | space @ Space::Empty => {
+                        seats.push((space, x, y));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        seats
+    }
+
+    // Every space row by row.
+    pub fn spaces(&self) -> Vec<Space> {
+        self.grid.iter().flatten().copied().collect()
+    }
+
+    pub fn occupied_count(&self) -> usize {
+        self.grid
+            .iter()
+            .flatten()
+            .filter(|&&s| s == Space::Occupied)
+            .count()
+    }
+}
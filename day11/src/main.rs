@@ -1,291 +1,249 @@
 #![allow(dead_code)]
-use anyhow::{Context, Result};
 use std::{
     env,
     error::Error,
-    fmt::{self, Display},
     fs::File,
-    io::{self, BufRead},
+    io::{self, BufWriter, Write},
     path::Path,
+    thread,
+    time::{Duration, Instant},
 };
 
-#[derive(Debug)]
-struct FloorMapError;
-impl Display for FloorMapError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "FloorMapError occurred")
-    }
-}
-
-impl Error for FloorMapError {}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Space {
-    Floor,
-    Empty,
-    Occupied,
+mod automaton;
+mod cycle;
+mod floor_map;
+mod render;
+mod simulation;
+
+use automaton::{adjacent, line_of_sight, seating_rule, Automaton};
+use cycle::{CycleDetector, Outcome};
+use floor_map::{FloorMap, Space};
+use simulation::SeatingSimulation;
+
+struct Options {
+    threshold: Option<usize>,
+    threads: usize,
+    max_steps: Option<usize>,
+    animate: bool,
+    // Milliseconds between generations when animating or exporting a gif.
+    delay: u16,
+    export_text: Option<String>,
+    export_gif: Option<String>,
+    // Pixels per space in an exported gif.
+    scale: usize,
 }
 
-struct FloorMap {
-    grid: Vec<Vec<Space>>,
-    width: usize,
-    height: usize,
+impl Options {
+    fn records_generations(&self) -> bool {
+        self.export_text.is_some() || self.export_gif.is_some()
+    }
 }
 
-impl FloorMap {
-    fn from_reader(reader: impl BufRead) -> Result<Self> {
-        let grid: Vec<Vec<Space>> = reader
-            .lines()
-            .map(|l| {
-                l.unwrap()
-                    .chars()
-                    .map(|c| match c {
-                        '.' => Space::Floor,
-                        'L' => Space::Empty,
-                        '#' => Space::Occupied,
-                        _ => std::process::exit(1),
-                    })
-                    .collect::<Vec<Space>>()
-            })
-            .collect();
-        let width = grid[0].len();
-        let height = grid.len();
-        for row in grid.iter() {
-            if row.len() != width {
-                return Err(FloorMapError).context("Not all lines of input were the same length");
-            }
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        threshold: None,
+        threads: 1,
+        max_steps: None,
+        animate: false,
+        delay: 200,
+        export_text: None,
+        export_gif: None,
+        scale: 4,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        if flag == "--animate" {
+            options.animate = true;
+            continue;
         }
-        Ok(FloorMap {
-            grid,
-            height,
-            width,
-        })
-    }
-
-    fn get(&self, x: usize, y: usize) -> Result<&Space> {
-        if x >= self.width || y >= self.height {
-            return Err(FloorMapError).context(format!(
-                "Attempted to get an out of bounds location: ({}, {})",
-                x, y
-            ));
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--threshold" => options.threshold = Some(value.parse()?),
+            "--threads" => options.threads = value.parse()?,
+            "--max-steps" => options.max_steps = Some(value.parse()?),
+            "--delay" => options.delay = value.parse()?,
+            "--export-text" => options.export_text = Some(value.clone()),
+            "--export-gif" => options.export_gif = Some(value.clone()),
+            "--scale" => options.scale = value.parse::<usize>()?.max(1),
+            _ => return Err(format!("unknown option {}", flag).into()),
         }
-        Ok(&self.grid[y][x])
     }
+    Ok(options)
+}
 
-    fn set(&mut self, x: usize, y: usize, val: Space) -> Result<()> {
-        if x >= self.width || y >= self.height {
-            return Err(FloorMapError).context(format!(
-                "Attempted to get an out of bounds location: ({}, {})",
-                x, y
-            ));
-        }
-        self.grid[y][x] = val;
-        Ok(())
-    }
+// Picks the seats a seat looks at, `adjacent` or `line_of_sight`.
+type Neighbourhood = fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>;
+
+fn print_generation<N, T>(
+    floor_map: &mut FloorMap,
+    simulation: &SeatingSimulation<N, T>,
+    generation: usize,
+) where
+    N: Fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>,
+    T: Fn(Space, &[Space]) -> Space,
+{
+    simulation.write_to(floor_map);
+    // Clear the screen and move the cursor home before redrawing.
+    print!("\x1b[2J\x1b[H");
+    println!(
+        "Generation {} ({} occupied)",
+        generation,
+        simulation.occupied_count()
+    );
+    floor_map.print_map(true);
+}
 
-    fn neighbours(&self, x: usize, y: usize) -> Result<Vec<Space>> {
-        if x >= self.width || y >= self.height {
-            return Err(FloorMapError).context(format!(
-                "Attempted to get an out of bounds location: ({}, {})",
-                x, y
-            ));
-        }
-        let mut valid_neigbours: Vec<Space> = Vec::new();
-        for x_offset in -1..2 {
-            for y_offset in -1..2 {
-                if x_offset != 0 || y_offset != 0 {
-                    match self.get(
-                        (x as i32 + x_offset) as usize,
-                        (y as i32 + y_offset) as usize,
-                    ) {
-                        Ok(space @ Space::Occupied) | Ok(space @ Space::Empty) => {
-                            valid_neigbours.push(*space)
-                        }
-                        _ => (),
-                    }
-                }
-            }
-        }
-        Ok(valid_neigbours)
+// Runs the simulation until it settles or repeats, one generation at a time
+// when animating or exporting, otherwise in one go. Returns how it ended and
+// the occupied seats in the last generation.
+fn simulate(
+    floor_map: &FloorMap,
+    neighbourhood: Neighbourhood,
+    threshold: usize,
+    options: &Options,
+) -> Result<(Outcome, usize), Box<dyn Error>> {
+    let automaton = Automaton::new(neighbourhood, seating_rule(threshold));
+    let mut simulation = SeatingSimulation::new(floor_map, automaton);
+    if !options.animate && !options.records_generations() {
+        let outcome = simulation.run(options.threads, options.max_steps)?;
+        return Ok((outcome, simulation.occupied_count()));
     }
-
-    fn neighbours_line_of_sight(&self, x: usize, y: usize) -> Result<Vec<Space>> {
-        if x >= self.width || y >= self.height {
-            return Err(FloorMapError).context(format!(
-                "Attempted to get an out of bounds location: ({}, {})",
-                x, y
-            ));
+    let mut display_map = floor_map.clone();
+    let mut generations: Vec<Vec<Space>> = Vec::new();
+    let mut detector = CycleDetector::new(options.max_steps);
+    let mut generation = 0;
+    let outcome = loop {
+        if let Some(outcome) = detector.observe(simulation.spaces())? {
+            break outcome;
         }
-
-        let mut valid_neigbours: Vec<Space> = Vec::new();
-        for (x_offset, y_offset) in [
-            (-1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-            (1, 0),
-            (1, -1),
-            (0, -1),
-            (-1, -1),
-        ]
-        .iter()
-        {
-            let mut new_x = x as i32;
-            let mut new_y = y as i32;
-            loop {
-                new_x += x_offset;
-                new_y += y_offset;
-                match self.get(new_x as usize, new_y as usize) {
-                    Ok(space @ Space::Occupied) | Ok(space @ Space::Empty) => {
-                        valid_neigbours.push(*space);
-                        break;
-                    }
-                    Err(_) => break,
-                    _ => continue,
-                }
-            }
+        if options.animate && generation > 0 {
+            thread::sleep(Duration::from_millis(options.delay as u64));
         }
-        Ok(valid_neigbours)
-    }
-
-    fn print_map(&self) -> () {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self.grid[y][x] {
-                    Space::Occupied => print!("#"),
-                    Space::Empty => print!("L"),
-                    Space::Floor => print!("."),
-                }
-            }
-            println!("");
+        if options.records_generations() {
+            generations.push(simulation.spaces().to_vec());
         }
-    }
-
-    fn seats_with_indices(&self) -> Vec<(Space, usize, usize)> {
-        let mut seats: Vec<(Space, usize, usize)> = Vec::new();
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self.grid[y][x] {
-                    space @ Space::Occupied | space @ Space::Empty => {
-                        seats.push((space, x, y));
-                    }
-                    _ => (),
-                }
-            }
+        if options.animate {
+            print_generation(&mut display_map, &simulation, generation);
         }
-        seats
+        simulation.step(options.threads);
+        generation += 1;
+    };
+    if let Some(path) = &options.export_text {
+        let mut out = BufWriter::new(File::create(path)?);
+        render::write_text(&mut out, &generations, simulation.width())?;
+        out.flush()?;
+    }
+    if let Some(path) = &options.export_gif {
+        let mut out = BufWriter::new(File::create(path)?);
+        render::write_gif(
+            &mut out,
+            &generations,
+            simulation.width(),
+            options.scale,
+            options.delay,
+        )?;
+        out.flush()?;
     }
+    Ok((outcome, simulation.occupied_count()))
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let input_path = Path::new("./input.txt");
-    let input_file = File::open(input_path)?;
-    let mut floor_map = FloorMap::from_reader(io::BufReader::new(input_file))?;
-
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        let stable_occupied = part1(&mut floor_map);
-        println!("Seats occupied in stable state {}", stable_occupied);
-        std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        let stable_occupied = part2(&mut floor_map);
-        println!("Seats occupied in stable state {}", stable_occupied);
-        std::process::exit(0);
-    } else {
-        println!("usage: cargo run (part1 | part2)");
-        std::process::exit(1);
+fn report(outcome: Outcome, occupied: usize) {
+    match outcome {
+        Outcome::Stable(_) => println!("Seats occupied in stable state {}", occupied),
+        Outcome::Cycle {
+            first_repeated,
+            period,
+        } => println!(
+            "Seating never settles, generation {} repeats every {} generations",
+            first_repeated, period
+        ),
     }
 }
 
-fn part1(floor_map: &mut FloorMap) -> i32 {
-    let mut updates: Vec<(usize, usize)> = Vec::new();
-    loop {
-        for (seat, x, y) in floor_map.seats_with_indices().iter() {
-            match seat {
-                Space::Empty => {
-                    if !floor_map
-                        .neighbours(*x, *y)
-                        .unwrap()
-                        .contains(&Space::Occupied)
-                    {
-                        updates.push((*x, *y));
-                    }
-                }
-                Space::Occupied => {
-                    if floor_map
-                        .neighbours(*x, *y)
-                        .unwrap()
-                        .iter()
-                        .filter(|&&s| s == Space::Occupied)
-                        .count()
-                        >= 4
-                    {
-                        updates.push((*x, *y));
-                    }
-                }
-                _ => unreachable!(),
-            }
+// Times the original automaton against the precomputed simulation, run with
+// --release for meaningful numbers.
+fn bench(floor_map: &FloorMap, iterations: u32) {
+    let threads = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let configs = [
+        ("part1", adjacent as Neighbourhood, 4),
+        ("part2", line_of_sight as Neighbourhood, 5),
+    ];
+    for &(name, neighbourhood, threshold) in configs.iter() {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let mut map = floor_map.clone();
+            Automaton::new(neighbourhood, seating_rule(threshold))
+                .run(&mut map, None)
+                .unwrap();
         }
-        if updates.len() == 0 {
-            break;
+        let automaton_time = start.elapsed() / iterations;
+        println!("{} automaton            {:?}", name, automaton_time);
+        let mut thread_counts = vec![1];
+        if threads > 1 {
+            thread_counts.push(threads);
         }
-        for (x, y) in updates.drain(..) {
-            if let Ok(Space::Empty) = floor_map.get(x, y) {
-                floor_map.set(x, y, Space::Occupied).unwrap();
-            } else {
-                floor_map.set(x, y, Space::Empty).unwrap();
+        for &thread_count in thread_counts.iter() {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                let automaton = Automaton::new(neighbourhood, seating_rule(threshold));
+                SeatingSimulation::new(floor_map, automaton)
+                    .run(thread_count, None)
+                    .unwrap();
             }
+            let simulation_time = start.elapsed() / iterations;
+            println!(
+                "{} simulation {:>2} threads {:?} ({:.1}x)",
+                name,
+                thread_count,
+                simulation_time,
+                automaton_time.as_secs_f64() / simulation_time.as_secs_f64()
+            );
         }
     }
-    floor_map
-        .seats_with_indices()
-        .iter()
-        .filter(|(s, _, _)| *s == Space::Occupied)
-        .count() as i32
 }
 
-fn part2(floor_map: &mut FloorMap) -> i32 {
-    let mut updates: Vec<(usize, usize)> = Vec::new();
-    loop {
-        for (seat, x, y) in floor_map.seats_with_indices().iter() {
-            match seat {
-                Space::Empty => {
-                    if !floor_map
-                        .neighbours_line_of_sight(*x, *y)
-                        .unwrap()
-                        .contains(&Space::Occupied)
-                    {
-                        updates.push((*x, *y));
-                    }
-                }
-                Space::Occupied => {
-                    if floor_map
-                        .neighbours_line_of_sight(*x, *y)
-                        .unwrap()
-                        .iter()
-                        .filter(|&&s| s == Space::Occupied)
-                        .count()
-                        >= 5
-                    {
-                        updates.push((*x, *y));
-                    }
-                }
-                _ => unreachable!(),
+fn main() -> Result<(), Box<dyn Error>> {
+    let input_path = Path::new("./input.txt");
+    let input_file = File::open(input_path)?;
+    let floor_map = FloorMap::from_reader(io::BufReader::new(input_file))?;
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.first() == Some(&String::from("part1")) {
+        let options = parse_options(&args[1..])?;
+        let threshold = options.threshold.unwrap_or(4);
+        match simulate(&floor_map, adjacent, threshold, &options) {
+            Ok((outcome, occupied)) => report(outcome, occupied),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
         }
-        if updates.len() == 0 {
-            break;
-        }
-        for (x, y) in updates.drain(..) {
-            if let Ok(Space::Empty) = floor_map.get(x, y) {
-                floor_map.set(x, y, Space::Occupied).unwrap();
-            } else {
-                floor_map.set(x, y, Space::Empty).unwrap();
+        std::process::exit(0);
+    } else if args.first() == Some(&String::from("part2")) {
+        let options = parse_options(&args[1..])?;
+        let threshold = options.threshold.unwrap_or(5);
+        match simulate(&floor_map, line_of_sight, threshold, &options) {
+            Ok((outcome, occupied)) => report(outcome, occupied),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
         }
+        std::process::exit(0);
+    } else if args.first() == Some(&String::from("bench")) {
+        let iterations = args
+            .get(1)
+            .map(|i| i.parse::<u32>())
+            .transpose()?
+            .unwrap_or(10);
+        bench(&floor_map, iterations.max(1));
+        std::process::exit(0);
+    } else {
+        println!("usage: cargo run (part1 | part2) [--threshold <occupied neighbours>] [--threads <count>] [--max-steps <generations>]");
+        println!("           [--animate] [--delay <ms>] [--export-text <path>] [--export-gif <path>] [--scale <pixels>]");
+        println!("       cargo run --release bench [iterations]");
+        std::process::exit(1);
     }
-    floor_map
-        .seats_with_indices()
-        .iter()
-        .filter(|(s, _, _)| *s == Space::Occupied)
-        .count() as i32
 }
@@ -0,0 +1,110 @@
+use crate::floor_map::Space;
+use std::{error::Error, io::Write};
+
+const PALETTE: [u8; 9] = [
+    0x40, 0x40, 0x40, // floor
+    0x2e, 0xa0, 0x43, // empty
+    0xd0, 0x30, 0x30, // occupied
+];
+
+fn palette_index(space: Space) -> u8 {
+    match space {
+        Space::Floor => 0,
+        Space::Empty => 1,
+        Space::Occupied => 2,
+    }
+}
+
+// Each generation under a "Generation N" heading, separated by blank lines.
+pub fn write_text(
+    out: &mut impl Write,
+    generations: &[Vec<Space>],
+    width: usize,
+) -> std::io::Result<()> {
+    for (generation, spaces) in generations.iter().enumerate() {
+        if generation > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "Generation {}", generation)?;
+        for row in spaces.chunks(width) {
+            writeln!(
+                out,
+                "{}",
+                row.iter().map(|s| s.symbol()).collect::<String>()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// An animated gif with one frame per generation, every space drawn as a
+// `scale` pixel square. The last frame is held for a second.
+pub fn write_gif(
+    out: &mut impl Write,
+    generations: &[Vec<Space>],
+    width: usize,
+    scale: usize,
+    delay_ms: u16,
+) -> Result<(), Box<dyn Error>> {
+    let height = generations.first().map_or(0, |g| g.len() / width);
+    let (image_width, image_height) = (width * scale, height * scale);
+    if image_width > u16::MAX as usize || image_height > u16::MAX as usize {
+        return Err("Floor map is too large for a gif at this scale".into());
+    }
+    let mut encoder = gif::Encoder::new(out, image_width as u16, image_height as u16, &PALETTE)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for (generation, spaces) in generations.iter().enumerate() {
+        let mut pixels = Vec::with_capacity(image_width * image_height);
+        for row in spaces.chunks(width) {
+            let scaled_row: Vec<u8> = row
+                .iter()
+                .flat_map(|&space| std::iter::repeat_n(palette_index(space), scale))
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&scaled_row);
+            }
+        }
+        let mut frame =
+            gif::Frame::from_indexed_pixels(image_width as u16, image_height as u16, &pixels, None);
+        frame.delay = if generation + 1 == generations.len() {
+            100
+        } else {
+            delay_ms / 10
+        };
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+mod test {
+    #[test]
+    fn text_export() {
+        use super::write_text;
+        use crate::floor_map::Space::{Empty, Floor, Occupied};
+        let generations = vec![
+            vec![Empty, Floor, Empty, Empty],
+            vec![Occupied, Floor, Occupied, Occupied],
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_text(&mut out, &generations, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Generation 0\nL.\nLL\n\nGeneration 1\n#.\n##\n"
+        );
+    }
+
+    #[test]
+    fn gif_export() {
+        use super::write_gif;
+        use crate::floor_map::Space::{Empty, Floor, Occupied};
+        let generations = vec![
+            vec![Empty, Floor, Empty, Empty],
+            vec![Occupied, Floor, Occupied, Occupied],
+        ];
+        let mut out: Vec<u8> = Vec::new();
+        write_gif(&mut out, &generations, 2, 3, 200).unwrap();
+        assert_eq!(&out[..6], b"GIF89a");
+        // Logical screen size is stored little endian after the signature.
+        assert_eq!(&out[6..10], &[6, 0, 6, 0]);
+    }
+}
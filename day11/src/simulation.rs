@@ -0,0 +1,223 @@
+use crate::automaton::Automaton;
+use crate::cycle::{CycleDetector, Outcome, StepLimitError};
+use crate::floor_map::{FloorMap, Space};
+use std::thread;
+
+// A faster way to run an `Automaton`. Seats never move, so each seat's
+// neighbourhood is found once up front and stored as flat indices. Each
+// generation reads `current` and writes `next`, then the buffers swap.
+pub struct SeatingSimulation<N, T>
+where
+    N: Fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>,
+    T: Fn(Space, &[Space]) -> Space,
+{
+    automaton: Automaton<N, T>,
+    width: usize,
+    height: usize,
+    // The neighbours of cell i are neighbour_indices[offsets[i]..offsets[i + 1]].
+    offsets: Vec<usize>,
+    neighbour_indices: Vec<usize>,
+    current: Vec<Space>,
+    next: Vec<Space>,
+}
+
+impl<N, T> SeatingSimulation<N, T>
+where
+    N: Fn(&FloorMap, usize, usize) -> Vec<(usize, usize)>,
+    T: Fn(Space, &[Space]) -> Space,
+{
+    pub fn new(floor_map: &FloorMap, automaton: Automaton<N, T>) -> Self {
+        let (width, height) = (floor_map.width(), floor_map.height());
+        let mut offsets = Vec::with_capacity(width * height + 1);
+        let mut neighbour_indices = Vec::new();
+        let mut current = Vec::with_capacity(width * height);
+        offsets.push(0);
+        for y in 0..height {
+            for x in 0..width {
+                let space = *floor_map.get(x, y).unwrap();
+                if space != Space::Floor {
+                    let positions = automaton.neighbours(floor_map, x, y);
+                    neighbour_indices.extend(positions.into_iter().map(|(x, y)| y * width + x));
+                }
+                offsets.push(neighbour_indices.len());
+                current.push(space);
+            }
+        }
+        SeatingSimulation {
+            automaton,
+            width,
+            height,
+            offsets,
+            neighbour_indices,
+            next: current.clone(),
+            current,
+        }
+    }
+
+    // Advances one generation, splitting the rows across `threads` threads when
+    // there's more than one. Returns the number of seats that changed.
+    pub fn step(&mut self, threads: usize) -> usize
+    where
+        N: Sync,
+        T: Sync,
+    {
+        let automaton = &self.automaton;
+        let current = &self.current;
+        let offsets = &self.offsets;
+        let neighbour_indices = &self.neighbour_indices;
+        let next = &mut self.next;
+        let update = |start: usize, chunk: &mut [Space]| -> usize {
+            let mut changed = 0;
+            let mut neighbours = Vec::new();
+            for (i, cell) in chunk.iter_mut().enumerate() {
+                let index = start + i;
+                if current[index] == Space::Floor {
+                    *cell = Space::Floor;
+                    continue;
+                }
+                neighbours.clear();
+                neighbours.extend(
+                    neighbour_indices[offsets[index]..offsets[index + 1]]
+                        .iter()
+                        .map(|&n| current[n]),
+                );
+                *cell = automaton.transition(current[index], &neighbours);
+                if *cell != current[index] {
+                    changed += 1;
+                }
+            }
+            changed
+        };
+        let changed = if threads <= 1 || self.height <= 1 {
+            update(0, next)
+        } else {
+            let rows_per_thread = self.height.div_ceil(threads);
+            let chunk_size = (rows_per_thread * self.width).max(1);
+            let update = &update;
+            thread::scope(|scope| {
+                let handles: Vec<_> = next
+                    .chunks_mut(chunk_size)
+                    .enumerate()
+                    .map(|(i, chunk)| scope.spawn(move || update(i * chunk_size, chunk)))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).sum()
+            })
+        };
+        std::mem::swap(&mut self.current, &mut self.next);
+        changed
+    }
+
+    // Steps until the layout settles or starts repeating, giving up with an
+    // error after `max_steps` generations.
+    pub fn run(
+        &mut self,
+        threads: usize,
+        max_steps: Option<usize>,
+    ) -> Result<Outcome, StepLimitError>
+    where
+        N: Sync,
+        T: Sync,
+    {
+        let mut detector = CycleDetector::new(max_steps);
+        loop {
+            if let Some(outcome) = detector.observe(&self.current)? {
+                return Ok(outcome);
+            }
+            self.step(threads);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // The current generation, row by row.
+    pub fn spaces(&self) -> &[Space] {
+        &self.current
+    }
+
+    pub fn occupied_count(&self) -> usize {
+        self.current
+            .iter()
+            .filter(|&&s| s == Space::Occupied)
+            .count()
+    }
+
+    // Copies the current generation back onto a floor map of the same size.
+    pub fn write_to(&self, floor_map: &mut FloorMap) {
+        for (index, space) in self.current.iter().enumerate() {
+            floor_map
+                .set(index % self.width, index / self.width, *space)
+                .unwrap();
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn matches_automaton() {
+        use super::SeatingSimulation;
+        use crate::automaton::{adjacent, line_of_sight, seating_rule, Automaton};
+        use crate::cycle::Outcome;
+        use crate::floor_map::FloorMap;
+        let input: &[u8] = include_bytes!("../test_input.txt");
+        let floor_map = FloorMap::from_reader(input).unwrap();
+        for threads in 1..4 {
+            let mut simulation =
+                SeatingSimulation::new(&floor_map, Automaton::new(adjacent, seating_rule(4)));
+            assert_eq!(simulation.run(threads, None), Ok(Outcome::Stable(5)));
+            assert_eq!(simulation.occupied_count(), 37);
+            let mut simulation =
+                SeatingSimulation::new(&floor_map, Automaton::new(line_of_sight, seating_rule(5)));
+            assert_eq!(simulation.run(threads, None), Ok(Outcome::Stable(6)));
+            assert_eq!(simulation.occupied_count(), 26);
+        }
+
+        // Each generation matches the reference engine, not just the end state.
+        let mut reference = FloorMap::from_reader(input).unwrap();
+        let automaton = Automaton::new(line_of_sight, seating_rule(3));
+        let mut simulation =
+            SeatingSimulation::new(&reference, Automaton::new(line_of_sight, seating_rule(3)));
+        let mut written = FloorMap::from_reader(input).unwrap();
+        for _ in 0..6 {
+            assert_eq!(automaton.step(&mut reference), simulation.step(2));
+            simulation.write_to(&mut written);
+            assert_eq!(written.seats_with_indices(), reference.seats_with_indices());
+        }
+    }
+
+    #[test]
+    fn oscillation() {
+        use super::SeatingSimulation;
+        use crate::automaton::{adjacent, seating_rule, Automaton};
+        use crate::cycle::{Outcome, StepLimitError};
+        use crate::floor_map::FloorMap;
+        let input: &[u8] = include_bytes!("../test_input.txt");
+        let floor_map = FloorMap::from_reader(input).unwrap();
+        // Every occupied seat empties straight away, so the seats flip forever.
+        let mut simulation =
+            SeatingSimulation::new(&floor_map, Automaton::new(adjacent, seating_rule(0)));
+        assert_eq!(
+            simulation.run(1, None),
+            Ok(Outcome::Cycle {
+                first_repeated: 0,
+                period: 2
+            })
+        );
+        let mut simulation =
+            SeatingSimulation::new(&floor_map, Automaton::new(adjacent, seating_rule(0)));
+        assert_eq!(
+            simulation.run(1, Some(1)),
+            Err(StepLimitError { max_steps: 1 })
+        );
+        let mut simulation =
+            SeatingSimulation::new(&floor_map, Automaton::new(adjacent, seating_rule(4)));
+        assert_eq!(
+            simulation.run(1, Some(5)),
+            Err(StepLimitError { max_steps: 5 })
+        );
+        let mut simulation =
+            SeatingSimulation::new(&floor_map, Automaton::new(adjacent, seating_rule(4)));
+        assert_eq!(simulation.run(1, Some(6)), Ok(Outcome::Stable(5)));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::route::Route;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Svg,
+    GeoJson,
+}
+
+impl Format {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "svg" => Some(Format::Svg),
+            "geojson" => Some(Format::GeoJson),
+            _ => None,
+        }
+    }
+}
+
+pub fn export(route: &Route, format: Format) -> String {
+    match format {
+        Format::Svg => to_svg(route),
+        Format::GeoJson => to_geojson(route),
+    }
+}
+
+fn points(path: impl Iterator<Item = (f64, f64)>) -> String {
+    path.map(|(x, y)| format!("{},{}", x, 0.0 - y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// SVG's y axis points down, so y is negated to keep north at the top. It's
+// subtracted from 0.0 so the origin isn't written as -0. The waypoint's path
+// is dashed and the start is marked with a dot.
+fn to_svg(route: &Route) -> String {
+    let bounds = route.bounding_box_with_waypoints();
+    let margin = (bounds.width().max(bounds.height()) * 0.05).ceil().max(1.0);
+    let stroke = margin / 5.0;
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        bounds.min_x - margin,
+        0.0 - bounds.max_y - margin,
+        bounds.width() + 2.0 * margin,
+        bounds.height() + 2.0 * margin
+    )
+    .unwrap();
+    if route.waypoint_at(0).is_some() {
+        writeln!(
+            out,
+            "  <polyline id=\"waypoint\" points=\"{}\" fill=\"none\" stroke=\"grey\" stroke-width=\"{}\" stroke-dasharray=\"{}\"/>",
+            points(route.waypoints()),
+            stroke,
+            stroke * 4.0
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "  <polyline id=\"ship\" points=\"{}\" fill=\"none\" stroke=\"navy\" stroke-width=\"{}\"/>",
+        points(route.positions()),
+        stroke
+    )
+    .unwrap();
+    let (x, y) = route.position_at(0).unwrap();
+    writeln!(
+        out,
+        "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"red\"/>",
+        x,
+        0.0 - y,
+        stroke * 3.0
+    )
+    .unwrap();
+    out.push_str("</svg>\n");
+    out
+}
+
+fn coordinates(path: impl Iterator<Item = (f64, f64)>) -> String {
+    path.map(|(x, y)| format!("[{}, {}]", x, y))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The puzzle's grid isn't on the globe, so coordinates are written as plain
+// east/north units rather than longitude and latitude.
+fn to_geojson(route: &Route) -> String {
+    let mut features = vec![format!(
+        "    {{\"type\": \"Feature\", \"properties\": {{\"name\": \"ship\"}}, \"geometry\": {{\"type\": \"LineString\", \"coordinates\": [{}]}}}}",
+        coordinates(route.positions())
+    )];
+    if route.waypoint_at(0).is_some() {
+        features.push(format!(
+            "    {{\"type\": \"Feature\", \"properties\": {{\"name\": \"waypoint\"}}, \"geometry\": {{\"type\": \"LineString\", \"coordinates\": [{}]}}}}",
+            coordinates(route.waypoints())
+        ));
+    }
+    format!(
+        "{{\n  \"type\": \"FeatureCollection\",\n  \"features\": [\n{}\n  ]\n}}\n",
+        features.join(",\n")
+    )
+}
+
+mod test {
+    #[test]
+    fn svg_and_geojson() {
+        use super::{export, Format};
+        use crate::ferry::{HeadingFerry, WaypointFerry};
+        use crate::navigation::parse_actions;
+        use crate::route::Route;
+        let actions = parse_actions("F10\nN3\nF7\nR90\nF11".lines().map(String::from)).unwrap();
+
+        let route = Route::follow(&mut HeadingFerry::new(), actions.iter().copied());
+        let svg = export(&route, Format::Svg);
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -4 19 13\">\n")
+        );
+        assert!(svg.contains("points=\"0,0 10,0 10,-3 17,-3 17,-3 17,8\""));
+        assert!(!svg.contains("waypoint"));
+        assert_eq!(
+            export(&route, Format::GeoJson),
+            "{
+  \"type\": \"FeatureCollection\",
+  \"features\": [
+    {\"type\": \"Feature\", \"properties\": {\"name\": \"ship\"}, \"geometry\": {\"type\": \"LineString\", \"coordinates\": [[0, 0], [10, 0], [10, 3], [17, 3], [17, 3], [17, -8]]}}
+  ]
+}
+"
+        );
+
+        let route = Route::follow(&mut WaypointFerry::new(), actions.iter().copied());
+        assert!(export(&route, Format::Svg).contains("id=\"waypoint\""));
+        assert!(export(&route, Format::GeoJson).contains("\"name\": \"waypoint\""));
+    }
+}
@@ -0,0 +1,206 @@
+use crate::navigation::{heading_vector, rotate, Direction, NavigationAction, NavigationModel};
+use crate::planner::{plan_heading_return, plan_waypoint_return};
+use std::fmt::{self, Display};
+
+// Part 1: the ferry faces a heading, moves steer the ferry itself and
+// rotations turn it. The heading is a bearing in degrees clockwise from north
+// so the ferry can sail in any direction, not just the four cardinal ones.
+#[derive(Clone)]
+pub struct HeadingFerry {
+    heading: i32,
+    x_coordinate: f64,
+    y_coordinate: f64,
+}
+
+impl HeadingFerry {
+    pub fn new() -> Self {
+        HeadingFerry {
+            heading: Direction::East.bearing(),
+            x_coordinate: 0.0,
+            y_coordinate: 0.0,
+        }
+    }
+
+    pub fn heading(&self) -> i32 {
+        self.heading
+    }
+}
+
+impl NavigationModel for HeadingFerry {
+    fn take_action(&mut self, action: NavigationAction) {
+        match action {
+            NavigationAction::Forward(m) => {
+                let (dx, dy) = heading_vector(self.heading);
+                self.x_coordinate += dx * m as f64;
+                self.y_coordinate += dy * m as f64;
+            }
+            NavigationAction::Move(d, m) => {
+                let (dx, dy) = d.offset();
+                self.x_coordinate += dx * m as f64;
+                self.y_coordinate += dy * m as f64;
+            }
+            NavigationAction::Rotate(degrees) => {
+                self.heading = (self.heading + degrees.rem_euclid(360)).rem_euclid(360)
+            }
+        }
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.x_coordinate, self.y_coordinate)
+    }
+
+    fn plan_return(&self) -> Option<Vec<NavigationAction>> {
+        plan_heading_return(self.position(), self.heading)
+    }
+}
+
+impl Display for HeadingFerry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Current position: ({}, {}), Current heading: {}°",
+            self.x_coordinate, self.y_coordinate, self.heading
+        )
+    }
+}
+
+// Part 2: moves and rotations act on a waypoint relative to the ferry, and
+// the ferry travels towards the waypoint.
+#[derive(Clone)]
+pub struct WaypointFerry {
+    x_coordinate: f64,
+    y_coordinate: f64,
+    waypoint_x_coordinate: f64,
+    waypoint_y_coordinate: f64,
+}
+
+impl WaypointFerry {
+    pub fn new() -> Self {
+        WaypointFerry {
+            x_coordinate: 0.0,
+            y_coordinate: 0.0,
+            waypoint_x_coordinate: 10.0,
+            waypoint_y_coordinate: 1.0,
+        }
+    }
+
+    pub fn waypoint(&self) -> (f64, f64) {
+        (self.waypoint_x_coordinate, self.waypoint_y_coordinate)
+    }
+}
+
+impl NavigationModel for WaypointFerry {
+    fn take_action(&mut self, action: NavigationAction) {
+        match action {
+            NavigationAction::Forward(m) => {
+                self.x_coordinate += m as f64 * self.waypoint_x_coordinate;
+                self.y_coordinate += m as f64 * self.waypoint_y_coordinate;
+            }
+            NavigationAction::Move(d, m) => {
+                let (dx, dy) = d.offset();
+                self.waypoint_x_coordinate += dx * m as f64;
+                self.waypoint_y_coordinate += dy * m as f64;
+            }
+            NavigationAction::Rotate(degrees) => {
+                let (x, y) = rotate(
+                    self.waypoint_x_coordinate,
+                    self.waypoint_y_coordinate,
+                    degrees,
+                );
+                self.waypoint_x_coordinate = x;
+                self.waypoint_y_coordinate = y;
+            }
+        }
+    }
+
+    fn position(&self) -> (f64, f64) {
+        (self.x_coordinate, self.y_coordinate)
+    }
+
+    fn plan_return(&self) -> Option<Vec<NavigationAction>> {
+        plan_waypoint_return(self.position(), self.waypoint())
+    }
+
+    fn waypoint_position(&self) -> Option<(f64, f64)> {
+        Some((
+            self.x_coordinate + self.waypoint_x_coordinate,
+            self.y_coordinate + self.waypoint_y_coordinate,
+        ))
+    }
+}
+
+impl Display for WaypointFerry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Current position: ({}, {}), Current waypoint: ({}, {})",
+            self.x_coordinate,
+            self.y_coordinate,
+            self.waypoint_x_coordinate,
+            self.waypoint_y_coordinate
+        )
+    }
+}
+
+mod test {
+    const EXAMPLE: &str = "F10
+N3
+F7
+R90
+F11";
+
+    #[test]
+    fn example() {
+        use super::{HeadingFerry, WaypointFerry};
+        use crate::navigation::{parse_actions, Direction, NavigationModel};
+        let actions = parse_actions(EXAMPLE.lines().map(String::from)).unwrap();
+        let mut heading = HeadingFerry::new();
+        let mut waypoint = WaypointFerry::new();
+        for &action in actions.iter() {
+            heading.take_action(action);
+            waypoint.take_action(action);
+        }
+        assert_eq!(heading.position(), (17.0, -8.0));
+        assert_eq!(heading.heading(), Direction::South.bearing());
+        assert_eq!(heading.displacement(), 25.0);
+        assert_eq!(waypoint.position(), (214.0, -72.0));
+        assert_eq!(waypoint.waypoint(), (4.0, -10.0));
+        assert_eq!(waypoint.displacement(), 286.0);
+    }
+
+    #[test]
+    fn huge_rotations() {
+        use super::HeadingFerry;
+        use crate::navigation::{NavigationAction, NavigationModel};
+        let mut heading = HeadingFerry::new();
+        heading.take_action(NavigationAction::Rotate(i32::MAX));
+        assert_eq!(heading.heading(), 217);
+        let mut heading = HeadingFerry::new();
+        heading.take_action(NavigationAction::Rotate(i32::MIN));
+        assert_eq!(heading.heading(), 322);
+    }
+
+    #[test]
+    fn arbitrary_angles() {
+        use super::{HeadingFerry, WaypointFerry};
+        use crate::navigation::{NavigationAction, NavigationModel};
+        let close = |(x, y): (f64, f64), (ex, ey): (f64, f64)| {
+            (x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9
+        };
+        // Turning 30 degrees left from east gives a 30-60-90 triangle.
+        let mut heading = HeadingFerry::new();
+        heading.take_action(NavigationAction::Rotate(-30));
+        heading.take_action(NavigationAction::Forward(2));
+        assert_eq!(heading.heading(), 60);
+        assert!(close(heading.position(), (3f64.sqrt(), 1.0)));
+
+        let mut waypoint = WaypointFerry::new();
+        waypoint.take_action(NavigationAction::Rotate(45));
+        waypoint.take_action(NavigationAction::Rotate(-45));
+        assert!(close(waypoint.waypoint(), (10.0, 1.0)));
+        waypoint.take_action(NavigationAction::Rotate(-135));
+        waypoint.take_action(NavigationAction::Forward(1));
+        let half = 0.5f64.sqrt();
+        assert!(close(waypoint.position(), (-11.0 * half, 9.0 * half)));
+    }
+}
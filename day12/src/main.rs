@@ -1,107 +1,126 @@
 #![allow(dead_code)]
 use std::{
+    env,
     error::Error,
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead},
     path::Path,
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Direction {
-    North,
-    East,
-    West,
-    South,
-}
+mod export;
+mod ferry;
+mod navigation;
+mod planner;
+mod route;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum NavigationAction {
-    Rotate(i32),
-    Move(Direction, i32),
-    Forward(i32)
-}
+use ferry::{HeadingFerry, WaypointFerry};
+use navigation::{parse_actions, NavigationModel};
+use route::Route;
 
-struct Ferry {
-    history: Vec<NavigationAction>,
-    x_coordinate: i32,
-    y_coordinate: i32,
-    waypoint_x_coordinate: i32,
-    waypoint_y_coordinate: i32,
+struct Options {
+    verbose: bool,
+    summary: bool,
+    plan: bool,
+    at: Option<usize>,
+    export: Option<(export::Format, String)>,
 }
 
-impl Ferry {
-    fn new() -> Self {
-        Ferry { history: Vec::new(), x_coordinate: 0, y_coordinate: 0, waypoint_x_coordinate: 10, waypoint_y_coordinate: 1 }
-    }
-    fn take_action(&mut self, a: NavigationAction) {
-        match a {
-            NavigationAction::Forward(m) => {
-                self.x_coordinate += m * self.waypoint_x_coordinate;
-                self.y_coordinate += m * self.waypoint_y_coordinate;
+fn parse_options(args: &[String]) -> Result<Options, Box<dyn Error>> {
+    let mut options = Options {
+        verbose: false,
+        summary: false,
+        plan: false,
+        at: None,
+        export: None,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--verbose" => options.verbose = true,
+            "--summary" => options.summary = true,
+            "--plan" => options.plan = true,
+            "--at" => options.at = Some(args.next().ok_or("--at needs a step")?.parse()?),
+            "--export" => {
+                let format = args.next().ok_or("--export needs a format")?;
+                let format = export::Format::from_str(format)
+                    .ok_or(format!("unknown export format {}", format))?;
+                let path = args.next().ok_or("--export needs a path")?;
+                options.export = Some((format, path.clone()));
             }
-            NavigationAction::Move(d, m) => {
-                match d {
-                    Direction::North => self.waypoint_y_coordinate += m,
-                    Direction::South => self.waypoint_y_coordinate -= m,
-                    Direction::West => self.waypoint_x_coordinate -= m,
-                    Direction::East => self.waypoint_x_coordinate += m,
-                }
-            }
-            NavigationAction::Rotate(m) => {
-                match m % 360 {
-                    180 | -180 => {
-                        self.waypoint_x_coordinate *= -1;
-                        self.waypoint_y_coordinate *= -1;
-                    }
-                    90 | -270 => {
-                        let temp = -self.waypoint_x_coordinate;
-                        self.waypoint_x_coordinate = self.waypoint_y_coordinate;
-                        self.waypoint_y_coordinate = temp;
-                    }
-                    -90 | 270 => {
-                        let temp = -self.waypoint_y_coordinate;
-                        self.waypoint_y_coordinate = self.waypoint_x_coordinate;
-                        self.waypoint_x_coordinate = temp;
-                    }
-                    _ => panic!("Non cardinal directions not allowed")
-                }
-            }
-        }
-    }
-    fn displacement(&self) -> i32 {
-        self.x_coordinate.abs() + self.y_coordinate.abs()
-    }
-    fn print_status(&self) {
-        println!("Current position: ({}, {}), Current waypoint: ({}, {})", self.x_coordinate, self.y_coordinate, self.waypoint_x_coordinate, self.waypoint_y_coordinate);
-    }
-}
-
-impl NavigationAction {
-    fn from_string(s: String) -> Self {
-        let (action, magnitude) = s.split_at(1);
-        match action {
-            "N" => NavigationAction::Move(Direction::North, magnitude.parse().unwrap()),
-            "S" => NavigationAction::Move(Direction::South, magnitude.parse().unwrap()),
-            "E" => NavigationAction::Move(Direction::East, magnitude.parse().unwrap()),
-            "W" => NavigationAction::Move(Direction::West, magnitude.parse().unwrap()),
-            "L" => NavigationAction::Rotate(-1 * magnitude.parse::<i32>().unwrap()),
-            "R" => NavigationAction::Rotate(magnitude.parse().unwrap()),
-            "F" => NavigationAction::Forward(magnitude.parse().unwrap()),
-            _ => unreachable!(),
+            _ => return Err(format!("unknown option {}", flag).into()),
         }
     }
+    Ok(options)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let mut ferry = Ferry::new();
-    ferry.print_status();
-    for action in io::BufReader::new(input_file).lines().map(|l| NavigationAction::from_string(l.unwrap())) {
+    let mut ferry: Box<dyn NavigationModel> = match args.first().map(String::as_str) {
+        Some("part1") => Box::new(HeadingFerry::new()),
+        Some("part2") => Box::new(WaypointFerry::new()),
+        _ => {
+            println!("usage: cargo run (part1 | part2) [--verbose] [--summary] [--plan] [--at <step>] [--export (svg | geojson) <path>]");
+            std::process::exit(1);
+        }
+    };
+    let options = parse_options(&args[1..])?;
+    let actions = match parse_actions(io::BufReader::new(input_file).lines().map(|l| l.unwrap())) {
+        Ok(actions) => actions,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if options.verbose {
+        println!("{}", ferry);
+    }
+    let mut route = Route::start(ferry.as_ref());
+    for action in actions {
         ferry.take_action(action);
-        ferry.print_status();
+        route.record(action, ferry.as_ref());
+        if options.verbose {
+            println!("{}", ferry);
+        }
+    }
+    println!(
+        "Manhattan distance from initial position {}",
+        ferry.displacement()
+    );
+    if options.summary {
+        let (step, distance) = route.max_distance();
+        let bounds = route.bounding_box();
+        println!("Furthest from the start {} after step {}", distance, step);
+        println!(
+            "Bounding box ({}, {}) to ({}, {})",
+            bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y
+        );
+    }
+    if let Some(step) = options.at {
+        match route.position_at(step) {
+            Some((x, y)) => println!("Position after step {}: ({}, {})", step, x, y),
+            None => println!("The route only has {} steps", route.len()),
+        }
+    }
+    if let Some((format, path)) = &options.export {
+        fs::write(path, export::export(&route, *format))?;
+    }
+    if options.plan {
+        match ferry.plan_return() {
+            Some(plan) => {
+                let text: Vec<String> = plan.iter().map(|a| a.to_string()).collect();
+                println!(
+                    "Return to origin with {} actions: {}",
+                    plan.len(),
+                    text.join(" ")
+                );
+                let verified = planner::returns_to_origin(ferry.as_mut(), &plan);
+                println!("Verified by following the plan: {}", verified);
+            }
+            None => println!("No exact return plan made of whole number actions"),
+        }
     }
-    println!("Manhattan distance from initial position {}", ferry.displacement());
     std::process::exit(0);
 }
@@ -0,0 +1,220 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    North,
+    East,
+    West,
+    South,
+}
+
+impl Direction {
+    // Unit vector with east as +x and north as +y.
+    pub fn offset(&self) -> (f64, f64) {
+        match self {
+            Direction::North => (0.0, 1.0),
+            Direction::East => (1.0, 0.0),
+            Direction::South => (0.0, -1.0),
+            Direction::West => (-1.0, 0.0),
+        }
+    }
+
+    // Degrees clockwise from north.
+    pub fn bearing(&self) -> i32 {
+        match self {
+            Direction::North => 0,
+            Direction::East => 90,
+            Direction::South => 180,
+            Direction::West => 270,
+        }
+    }
+}
+
+// Rotates (x, y) clockwise by `degrees`. Quarter turns just swap and negate
+// coordinates so integer positions stay exact, any other angle goes through
+// floating point trig.
+pub fn rotate(x: f64, y: f64, degrees: i32) -> (f64, f64) {
+    if degrees % 90 == 0 {
+        match degrees.rem_euclid(360) {
+            0 => (x, y),
+            90 => (y, -x),
+            180 => (-x, -y),
+            _ => (-y, x),
+        }
+    } else {
+        let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+        (x * cos + y * sin, y * cos - x * sin)
+    }
+}
+
+// Unit vector pointing along a bearing in degrees clockwise from north.
+pub fn heading_vector(bearing: i32) -> (f64, f64) {
+    rotate(0.0, 1.0, bearing)
+}
+
+// Rotations are in degrees, positive is clockwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NavigationAction {
+    Rotate(i32),
+    Move(Direction, i32),
+    Forward(i32),
+}
+
+impl NavigationAction {
+    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+        let mut chars = s.chars();
+        let action = chars.next().ok_or("empty action")?;
+        let magnitude: i32 = chars
+            .as_str()
+            .parse()
+            .map_err(|_| "magnitude is not an integer")?;
+        Ok(match action {
+            'N' => NavigationAction::Move(Direction::North, magnitude),
+            'S' => NavigationAction::Move(Direction::South, magnitude),
+            'E' => NavigationAction::Move(Direction::East, magnitude),
+            'W' => NavigationAction::Move(Direction::West, magnitude),
+            'L' => {
+                NavigationAction::Rotate(magnitude.checked_neg().ok_or("rotation is out of range")?)
+            }
+            'R' => NavigationAction::Rotate(magnitude),
+            'F' => NavigationAction::Forward(magnitude),
+            _ => return Err("unknown action"),
+        })
+    }
+}
+
+// Written back in the puzzle's notation, anticlockwise rotations use L.
+impl Display for NavigationAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NavigationAction::Rotate(degrees) if *degrees < 0 => {
+                write!(f, "L{}", degrees.unsigned_abs())
+            }
+            NavigationAction::Rotate(degrees) => write!(f, "R{}", degrees),
+            NavigationAction::Move(direction, m) => {
+                let letter = match direction {
+                    Direction::North => 'N',
+                    Direction::East => 'E',
+                    Direction::West => 'W',
+                    Direction::South => 'S',
+                };
+                write!(f, "{}{}", letter, m)
+            }
+            NavigationAction::Forward(m) => write!(f, "F{}", m),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} \"{}\"", self.line, self.reason, self.text)
+    }
+}
+
+impl Error for ParseError {}
+
+// Blank lines are skipped but still counted so line numbers match the file.
+pub fn parse_actions(
+    lines: impl Iterator<Item = String>,
+) -> Result<Vec<NavigationAction>, ParseError> {
+    let mut actions = Vec::new();
+    for (index, text) in lines.enumerate() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let action = NavigationAction::from_str(text).map_err(|reason| ParseError {
+            line: index + 1,
+            text: String::from(text),
+            reason,
+        })?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+// Something that can follow navigation instructions. The ferry starts at the
+// origin with east as +x and north as +y, what the actions mean is up to the
+// model. Positions only stop being whole numbers once a rotation that isn't a
+// multiple of 90 degrees comes along.
+pub trait NavigationModel: Display {
+    fn take_action(&mut self, action: NavigationAction);
+
+    fn position(&self) -> (f64, f64);
+
+    // Where the waypoint is in absolute coordinates, for models that have one.
+    fn waypoint_position(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    // The fewest actions that bring the ferry back to the origin, see the
+    // planner module.
+    fn plan_return(&self) -> Option<Vec<NavigationAction>>;
+
+    fn displacement(&self) -> f64 {
+        let (x, y) = self.position();
+        x.abs() + y.abs()
+    }
+}
+
+mod test {
+    #[test]
+    fn parsing() {
+        use super::{parse_actions, Direction, NavigationAction, ParseError};
+        let actions = parse_actions("F10\nN3\n\nL270\nR45".lines().map(String::from)).unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                NavigationAction::Forward(10),
+                NavigationAction::Move(Direction::North, 3),
+                NavigationAction::Rotate(-270),
+                NavigationAction::Rotate(45),
+            ]
+        );
+        assert_eq!(
+            parse_actions("F10\nRx".lines().map(String::from)),
+            Err(ParseError {
+                line: 2,
+                text: String::from("Rx"),
+                reason: "magnitude is not an integer"
+            })
+        );
+        assert_eq!(NavigationAction::from_str("X1"), Err("unknown action"));
+        assert_eq!(
+            NavigationAction::from_str("R2147483647"),
+            Ok(NavigationAction::Rotate(i32::MAX))
+        );
+        assert_eq!(
+            NavigationAction::from_str("L-2147483648"),
+            Err("rotation is out of range")
+        );
+        assert_eq!(
+            NavigationAction::Rotate(i32::MIN).to_string(),
+            "L2147483648"
+        );
+    }
+
+    #[test]
+    fn rotation() {
+        use super::{heading_vector, rotate};
+        // Quarter turns are exact, even for large or negative angles.
+        assert_eq!(rotate(10.0, 4.0, 90), (4.0, -10.0));
+        assert_eq!(rotate(10.0, 4.0, -270), (4.0, -10.0));
+        assert_eq!(rotate(10.0, 4.0, 720), (10.0, 4.0));
+        assert_eq!(heading_vector(180), (0.0, -1.0));
+        let (x, y) = heading_vector(45);
+        assert!((x - 0.5f64.sqrt()).abs() < 1e-12 && (y - 0.5f64.sqrt()).abs() < 1e-12);
+        let (x, y) = rotate(3.0, 0.0, -30);
+        assert!((x - 1.5 * 3f64.sqrt()).abs() < 1e-12 && (y - 1.5).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::navigation::{heading_vector, rotate, Direction, NavigationAction, NavigationModel};
+
+// How far a position may be from the origin and still count as there, float
+// mode rotations leave a little rounding error behind.
+const EPSILON: f64 = 1e-6;
+
+fn whole(value: f64) -> Option<i32> {
+    let rounded = value.round();
+    if (value - rounded).abs() < EPSILON && rounded.abs() <= i32::MAX as f64 {
+        Some(rounded as i32)
+    } else {
+        None
+    }
+}
+
+// A move of `delta` along one axis, None when there's nothing to do. Moves
+// always have a positive magnitude so they read like puzzle input.
+fn move_along(delta: i32, horizontal: bool) -> Option<NavigationAction> {
+    let direction = match (horizontal, delta > 0) {
+        (true, true) => Direction::East,
+        (true, false) => Direction::West,
+        (false, true) => Direction::North,
+        (false, false) => Direction::South,
+    };
+    match delta {
+        0 => None,
+        _ => Some(NavigationAction::Move(direction, delta.abs())),
+    }
+}
+
+// The positive whole number m with target = m * step, if there is one.
+fn multiple(target: (f64, f64), step: (f64, f64)) -> Option<i32> {
+    let ratio = if step.0.abs() > step.1.abs() {
+        target.0 / step.0
+    } else if step.1 != 0.0 {
+        target.1 / step.1
+    } else {
+        return None;
+    };
+    let m = whole(ratio)?;
+    if m >= 1
+        && (target.0 - m as f64 * step.0).abs() < EPSILON
+        && (target.1 - m as f64 * step.1).abs() < EPSILON
+    {
+        Some(m)
+    } else {
+        None
+    }
+}
+
+// Degrees clockwise from north, in (-180, 180].
+fn bearing_of((x, y): (f64, f64)) -> f64 {
+    x.atan2(y).to_degrees()
+}
+
+fn at_origin((x, y): (f64, f64)) -> bool {
+    x.abs() < EPSILON && y.abs() < EPSILON
+}
+
+fn axis_moves(target: (f64, f64)) -> Option<Vec<NavigationAction>> {
+    let (dx, dy) = (whole(target.0)?, whole(target.1)?);
+    Some(
+        move_along(dx, true)
+            .into_iter()
+            .chain(move_along(dy, false))
+            .collect(),
+    )
+}
+
+// The turn in (-180, 180] that points `step` at `target`, and how many steps
+// then reach it, when both come out whole.
+fn turn_towards(target: (f64, f64), step: (f64, f64)) -> Option<(i32, i32)> {
+    if step == (0.0, 0.0) {
+        return None;
+    }
+    let mut degrees = (bearing_of(target) - bearing_of(step)).round() as i32;
+    if degrees > 180 {
+        degrees -= 360;
+    } else if degrees <= -180 {
+        degrees += 360;
+    }
+    let m = multiple(target, rotate(step.0, step.1, degrees))?;
+    Some((degrees, m))
+}
+
+// A heading ferry at a whole number position gets home with at most one move
+// along each axis. A single forward never beats one axis move there: whole
+// number positions off the axes are never exactly along a whole degree
+// heading, since the only rational sines and cosines of whole degrees are 0,
+// 1/2 and 1 (Niven's theorem). Off the whole number grid, which turns that
+// aren't quarter turns lead to, only a forward, or a turn then a forward, can
+// get it home exactly.
+pub fn plan_heading_return(position: (f64, f64), heading: i32) -> Option<Vec<NavigationAction>> {
+    let target = (-position.0, -position.1);
+    let moves = axis_moves(target);
+    if let Some(moves) = moves.as_ref().filter(|moves| moves.len() <= 1) {
+        return Some(moves.clone());
+    }
+    let direction = heading_vector(heading);
+    if let Some(m) = multiple(target, direction) {
+        return Some(vec![NavigationAction::Forward(m)]);
+    }
+    moves.or_else(|| {
+        let (degrees, m) = turn_towards(target, direction)?;
+        Some(vec![
+            NavigationAction::Rotate(degrees),
+            NavigationAction::Forward(m),
+        ])
+    })
+}
+
+// Only forwards move a waypoint ferry, so every plan ends in one. In order of
+// length it tries: a forward on the current waypoint, a rotation or a single
+// axis move to line the waypoint up with the origin then a forward, and
+// finally moving the waypoint onto the origin's offset and going forward once.
+// Returns None when none of those work out in whole numbers, which only
+// happens once a turn that isn't a quarter turn has moved things off the grid.
+pub fn plan_waypoint_return(
+    position: (f64, f64),
+    waypoint: (f64, f64),
+) -> Option<Vec<NavigationAction>> {
+    if at_origin(position) {
+        return Some(Vec::new());
+    }
+    let target = (-position.0, -position.1);
+    if let Some(m) = multiple(target, waypoint) {
+        return Some(vec![NavigationAction::Forward(m)]);
+    }
+    if let Some((degrees, m)) = turn_towards(target, waypoint) {
+        return Some(vec![
+            NavigationAction::Rotate(degrees),
+            NavigationAction::Forward(m),
+        ]);
+    }
+
+    // Keep one waypoint coordinate, pick the forward count it needs and move
+    // the other coordinate to match.
+    for &horizontal in [true, false].iter() {
+        let (kept, kept_target, other, other_target) = if horizontal {
+            (waypoint.0, target.0, waypoint.1, target.1)
+        } else {
+            (waypoint.1, target.1, waypoint.0, target.0)
+        };
+        let m = if kept == 0.0 && kept_target == 0.0 {
+            1
+        } else if kept == 0.0 {
+            continue;
+        } else {
+            match whole(kept_target / kept) {
+                Some(m) if m >= 1 => m,
+                _ => continue,
+            }
+        };
+        if (kept_target - m as f64 * kept).abs() >= EPSILON {
+            continue;
+        }
+        if let Some(delta) = whole(other_target / m as f64 - other) {
+            return Some(
+                move_along(delta, !horizontal)
+                    .into_iter()
+                    .chain(Some(NavigationAction::Forward(m)))
+                    .collect(),
+            );
+        }
+    }
+
+    let mut plan = axis_moves((target.0 - waypoint.0, target.1 - waypoint.1))?;
+    plan.push(NavigationAction::Forward(1));
+    Some(plan)
+}
+
+// Runs `plan` through the model and checks it ends up at the origin.
+pub fn returns_to_origin(model: &mut dyn NavigationModel, plan: &[NavigationAction]) -> bool {
+    for &action in plan {
+        model.take_action(action);
+    }
+    at_origin(model.position())
+}
+
+mod test {
+    fn actions(text: &str) -> Vec<crate::navigation::NavigationAction> {
+        crate::navigation::parse_actions(text.lines().map(String::from)).unwrap()
+    }
+
+    fn plan_text(model: &dyn crate::navigation::NavigationModel) -> String {
+        let plan = model.plan_return().unwrap();
+        plan.iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn heading_plans() {
+        use super::returns_to_origin;
+        use crate::ferry::HeadingFerry;
+        use crate::navigation::NavigationModel;
+        use crate::route::Route;
+        let mut ferry = HeadingFerry::new();
+        assert_eq!(plan_text(&ferry), "");
+        Route::follow(&mut ferry, actions("F10\nN3\nF7\nR90\nF11"));
+        assert_eq!(plan_text(&ferry), "W17 N8");
+        let plan = ferry.plan_return().unwrap();
+        assert!(returns_to_origin(&mut ferry.clone(), &plan));
+
+        let mut ferry = HeadingFerry::new();
+        Route::follow(&mut ferry, actions("N4\nE3"));
+        assert_eq!(plan_text(&ferry), "W3 S4");
+        Route::follow(&mut ferry, actions("W3"));
+        assert_eq!(plan_text(&ferry), "S4");
+
+        // The heading plays no part.
+        let mut ferry = HeadingFerry::new();
+        Route::follow(&mut ferry, actions("N5\nE5\nR135"));
+        assert_eq!(plan_text(&ferry), "W5 S5");
+
+        // Off the whole number grid only forwards can get home.
+        for &(route, expected) in [("L45\nF10", "R180 F10"), ("L60\nF4\nR180", "F4")].iter() {
+            let mut ferry = HeadingFerry::new();
+            Route::follow(&mut ferry, actions(route));
+            assert_eq!(plan_text(&ferry), expected, "after {:?}", route);
+            let plan = ferry.plan_return().unwrap();
+            assert!(returns_to_origin(&mut ferry.clone(), &plan));
+        }
+        let mut ferry = HeadingFerry::new();
+        Route::follow(&mut ferry, actions("L45\nF10\nR45\nF1"));
+        assert_eq!(ferry.plan_return(), None);
+    }
+
+    #[test]
+    fn waypoint_plans() {
+        use super::returns_to_origin;
+        use crate::ferry::WaypointFerry;
+        use crate::navigation::NavigationModel;
+        use crate::route::Route;
+        let cases = [
+            ("", ""),
+            // Already heading straight back.
+            ("F1\nR180", "F1"),
+            // A quarter turn lines the waypoint up.
+            ("F1\nL90", "L90 F1"),
+            // Keeping x means going forward twice, with y fixed up first.
+            ("F2\nW20\nN4", "S6 F2"),
+            // Nothing lines up, so the waypoint goes straight to the origin.
+            ("F1\nE7\nN3", "W27 S5 F1"),
+            // Off the whole number grid after turning an eighth.
+            ("R45\nF1", "R180 F1"),
+            ("R45\nF3\nL180", "F3"),
+        ];
+        for &(route, expected) in cases.iter() {
+            let mut ferry = WaypointFerry::new();
+            Route::follow(&mut ferry, actions(route));
+            assert_eq!(plan_text(&ferry), expected, "after {:?}", route);
+            let plan = ferry.plan_return().unwrap();
+            assert!(returns_to_origin(&mut ferry.clone(), &plan));
+        }
+    }
+}
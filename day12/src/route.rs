@@ -0,0 +1,190 @@
+use crate::navigation::{NavigationAction, NavigationModel};
+
+// Where everything was after an action.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteStep {
+    pub action: NavigationAction,
+    pub position: (f64, f64),
+    pub waypoint: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    fn around(point: (f64, f64)) -> Self {
+        BoundingBox {
+            min_x: point.0,
+            min_y: point.1,
+            max_x: point.0,
+            max_y: point.1,
+        }
+    }
+
+    fn include(&mut self, (x, y): (f64, f64)) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+}
+
+// Everything a ferry did on its way through a list of actions. Step 0 is the
+// starting state, step n is the state after the nth action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    start: (f64, f64),
+    start_waypoint: Option<(f64, f64)>,
+    history: Vec<RouteStep>,
+}
+
+impl Route {
+    pub fn start(model: &dyn NavigationModel) -> Self {
+        Route {
+            start: model.position(),
+            start_waypoint: model.waypoint_position(),
+            history: Vec::new(),
+        }
+    }
+
+    // Call after `model` has taken `action`.
+    pub fn record(&mut self, action: NavigationAction, model: &dyn NavigationModel) {
+        self.history.push(RouteStep {
+            action,
+            position: model.position(),
+            waypoint: model.waypoint_position(),
+        });
+    }
+
+    // Runs every action through `model`, recording as it goes.
+    pub fn follow(
+        model: &mut dyn NavigationModel,
+        actions: impl IntoIterator<Item = NavigationAction>,
+    ) -> Self {
+        let mut route = Route::start(model);
+        for action in actions {
+            model.take_action(action);
+            route.record(action, model);
+        }
+        route
+    }
+
+    pub fn history(&self) -> &[RouteStep] {
+        &self.history
+    }
+
+    // Number of actions taken.
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn position_at(&self, step: usize) -> Option<(f64, f64)> {
+        match step {
+            0 => Some(self.start),
+            _ => self.history.get(step - 1).map(|s| s.position),
+        }
+    }
+
+    pub fn waypoint_at(&self, step: usize) -> Option<(f64, f64)> {
+        match step {
+            0 => self.start_waypoint,
+            _ => self.history.get(step - 1).and_then(|s| s.waypoint),
+        }
+    }
+
+    // Every position from the start onwards.
+    pub fn positions(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        (0..=self.len()).map(move |step| self.position_at(step).unwrap())
+    }
+
+    // Every waypoint from the start onwards, empty if the model has none.
+    pub fn waypoints(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        (0..=self.len()).filter_map(move |step| self.waypoint_at(step))
+    }
+
+    // The step at which the ferry was furthest from where it started, by
+    // Manhattan distance, and that distance. The earliest step wins ties.
+    pub fn max_distance(&self) -> (usize, f64) {
+        let (x0, y0) = self.start;
+        self.positions()
+            .map(|(x, y)| (x - x0).abs() + (y - y0).abs())
+            .enumerate()
+            .fold((0, 0.0), |best, (step, distance)| {
+                if distance > best.1 {
+                    (step, distance)
+                } else {
+                    best
+                }
+            })
+    }
+
+    // Smallest box holding every position the ferry passed through.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::around(self.start);
+        for position in self.positions() {
+            bounds.include(position);
+        }
+        bounds
+    }
+
+    // Like `bounding_box` but also taking in the waypoint's path.
+    pub fn bounding_box_with_waypoints(&self) -> BoundingBox {
+        let mut bounds = self.bounding_box();
+        for waypoint in self.waypoints() {
+            bounds.include(waypoint);
+        }
+        bounds
+    }
+}
+
+mod test {
+    #[test]
+    fn example_route() {
+        use super::{BoundingBox, Route};
+        use crate::ferry::{HeadingFerry, WaypointFerry};
+        use crate::navigation::{parse_actions, NavigationAction};
+        let actions = parse_actions("F10\nN3\nF7\nR90\nF11".lines().map(String::from)).unwrap();
+
+        let route = Route::follow(&mut HeadingFerry::new(), actions.iter().copied());
+        assert_eq!(route.len(), 5);
+        assert_eq!(route.position_at(0), Some((0.0, 0.0)));
+        assert_eq!(route.position_at(3), Some((17.0, 3.0)));
+        assert_eq!(route.position_at(6), None);
+        assert_eq!(route.waypoint_at(2), None);
+        assert_eq!(route.history()[3].action, NavigationAction::Rotate(90));
+        assert_eq!(route.max_distance(), (5, 25.0));
+        assert_eq!(
+            route.bounding_box(),
+            BoundingBox {
+                min_x: 0.0,
+                min_y: -8.0,
+                max_x: 17.0,
+                max_y: 3.0
+            }
+        );
+
+        let route = Route::follow(&mut WaypointFerry::new(), actions.iter().copied());
+        assert_eq!(route.waypoint_at(0), Some((10.0, 1.0)));
+        assert_eq!(route.waypoint_at(2), Some((110.0, 14.0)));
+        assert_eq!(route.position_at(3), Some((170.0, 38.0)));
+        assert_eq!(route.max_distance(), (5, 286.0));
+        assert_eq!(route.bounding_box_with_waypoints().max_x, 218.0);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+// x ≡ residue (mod modulus)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Congruence {
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+// Every solution is `value + k * period`, `value` is the smallest one that
+// isn't negative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Solution {
+    pub value: i128,
+    pub period: i128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrtError {
+    // Moduli have to be positive.
+    InvalidModulus { index: usize, modulus: i128 },
+    // Congruence `index` contradicts the ones before it, which can happen
+    // when moduli share a factor.
+    NoSolution { index: usize },
+    // The combined modulus doesn't fit in an i128.
+    Overflow { index: usize },
+}
+
+impl Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::InvalidModulus { index, modulus } => {
+                write!(
+                    f,
+                    "congruence {} has modulus {}, which isn't positive",
+                    index, modulus
+                )
+            }
+            CrtError::NoSolution { index } => write!(
+                f,
+                "congruence {} can't be satisfied together with the ones before it",
+                index
+            ),
+            CrtError::Overflow { index } => write!(
+                f,
+                "the combined modulus overflows an i128 at congruence {}",
+                index
+            ),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
+// Returns (g, p, q) with g = gcd(a, b) = p * a + q * b.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_p, mut p) = (1, 0);
+    let (mut old_q, mut q) = (0, 1);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_p, p) = (p, old_p - quotient * p);
+        (old_q, q) = (q, old_q - quotient * q);
+    }
+    (old_r, old_p, old_q)
+}
+
+// Folds the congruences together one at a time. Combining x ≡ a (mod m) with
+// x ≡ b (mod n) only works if g = gcd(m, n) divides b - a, in which case
+// x = a + m * k where k solves (m / g) * k ≡ (b - a) / g (mod n / g), and the
+// result is taken modulo lcm(m, n). With no congruences every integer is a
+// solution, so that's 0 with period 1.
+pub fn solve(congruences: &[Congruence]) -> Result<Solution, CrtError> {
+    let mut solution = Solution {
+        value: 0,
+        period: 1,
+    };
+    for (index, congruence) in congruences.iter().enumerate() {
+        let (b, n) = (congruence.residue, congruence.modulus);
+        if n <= 0 {
+            return Err(CrtError::InvalidModulus { index, modulus: n });
+        }
+        let (a, m) = (solution.value, solution.period);
+        let (g, p, _) = extended_gcd(m, n);
+        let difference = b.rem_euclid(n) - a.rem_euclid(n);
+        if difference % g != 0 {
+            return Err(CrtError::NoSolution { index });
+        }
+        let reduced = n / g;
+        // p is the inverse of m / g modulo n / g, both factors are below
+        // n / g so only enormous moduli can overflow the product.
+        let k = (difference / g)
+            .rem_euclid(reduced)
+            .checked_mul(p.rem_euclid(reduced))
+            .ok_or(CrtError::Overflow { index })?
+            .rem_euclid(reduced);
+        let period = m.checked_mul(reduced).ok_or(CrtError::Overflow { index })?;
+        solution = Solution {
+            value: (a + m * k).rem_euclid(period),
+            period,
+        };
+    }
+    Ok(solution)
+}
+
+mod test {
+    fn congruences(pairs: &[(i128, i128)]) -> Vec<super::Congruence> {
+        pairs
+            .iter()
+            .map(|&(residue, modulus)| super::Congruence { residue, modulus })
+            .collect()
+    }
+
+    #[test]
+    fn coprime() {
+        use super::{solve, Solution};
+        assert_eq!(
+            solve(&congruences(&[(2, 3), (3, 5), (2, 7)])),
+            Ok(Solution {
+                value: 23,
+                period: 105
+            })
+        );
+        assert_eq!(
+            solve(&[]),
+            Ok(Solution {
+                value: 0,
+                period: 1
+            })
+        );
+    }
+
+    #[test]
+    fn non_coprime() {
+        use super::{solve, CrtError, Solution};
+        assert_eq!(
+            solve(&congruences(&[(2, 6), (4, 8)])),
+            Ok(Solution {
+                value: 20,
+                period: 24
+            })
+        );
+        assert_eq!(
+            solve(&congruences(&[(-1, 4), (3, 6), (7, 10)])),
+            Ok(Solution {
+                value: 27,
+                period: 60
+            })
+        );
+        assert_eq!(
+            solve(&congruences(&[(1, 3), (1, 4), (2, 6)])),
+            Err(CrtError::NoSolution { index: 2 })
+        );
+        assert_eq!(
+            solve(&congruences(&[(1, 3), (1, 0)])),
+            Err(CrtError::InvalidModulus {
+                index: 1,
+                modulus: 0
+            })
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        use super::{solve, CrtError};
+        let big = (1i128 << 100) - 1;
+        assert_eq!(
+            solve(&congruences(&[(1, big), (2, big - 2)])),
+            Err(CrtError::Overflow { index: 1 })
+        );
+    }
+}
@@ -1,73 +1,231 @@
 #![allow(dead_code)]
-use std::{thread, sync::mpsc::channel, env, fs::File, io::{self, BufRead}, path::Path};
-use ring_algorithm::chinese_remainder_theorem;
+use std::{
+    convert::TryFrom,
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
-fn main() -> Result<(), io::Error> {
+mod crt;
+mod schedule;
+mod search;
+
+use crt::Solution;
+use schedule::{AlignError, Bus, Schedule};
+use search::{search, SearchOptions, SearchResult};
+
+fn usage() -> ! {
+    println!("usage: cargo run (part1 | part2)");
+    println!("       cargo run --release part2slow [--threads <count>] [--limit <timestamp>] [--timeout <seconds>]");
+    println!("       cargo run next [time]");
+    println!("       cargo run (departures | timetable) <start> <end>");
+    println!("       cargo run align <bus>:<offset>[,<bus>:<offset>...]");
+    std::process::exit(1);
+}
+
+// Parses "7:0,13:1" into (bus id, offset) pairs.
+fn parse_requirements(s: &str) -> Result<Vec<(i64, i64)>, Box<dyn Error>> {
+    s.split(',')
+        .map(|pair| {
+            let (id, offset) = pair
+                .split_once(':')
+                .ok_or(format!("\"{}\" should look like <bus>:<offset>", pair))?;
+            Ok((id.parse()?, offset.parse()?))
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
-    let mut lines = io::BufReader::new(input_file).lines();
-    let earliest_departure = lines.next().unwrap().unwrap().parse::<i64>().unwrap();
-    let bus_id_string = lines.next().unwrap().unwrap();
-    let bus_ids: Vec<&str> = bus_id_string.split(',').collect();
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        let (earliest_bus_id, wait_time) = part1(earliest_departure, &bus_ids);
-        println!("{}", earliest_bus_id * wait_time);
-        std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        let timestamp = part2(&bus_ids);
-        println!("timestamp {}", timestamp);
-        std::process::exit(0);
-    } else {
-        println!("usage: cargo run (part1 | part2)");
-        std::process::exit(1);
+    let lines = io::BufReader::new(input_file).lines().map(|l| l.unwrap());
+    let schedule = match Schedule::from_lines(lines) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let args: Vec<String> = env::args().skip(1).collect();
+    let time_arg = |index: usize| -> Result<i64, Box<dyn Error>> {
+        Ok(args.get(index).ok_or("missing time")?.parse()?)
+    };
+    match args.first().map(String::as_str) {
+        Some("part1") => {
+            let (bus, wait_time) = part1(&schedule);
+            println!("{}", bus.id * wait_time);
+        }
+        Some("part2") => match part2(&schedule) {
+            Ok(solution) => {
+                println!("timestamp {}", solution.value);
+                println!("repeats every {}", solution.period);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        Some("part2slow") => part2slow(&schedule, &args[1..])?,
+        Some("next") => {
+            let time = match args.get(1) {
+                Some(time) => time.parse()?,
+                None => schedule.earliest_departure,
+            };
+            for (bus, departure) in schedule.next_departures(time) {
+                println!(
+                    "bus {} leaves at {}, {} minutes wait",
+                    bus.id,
+                    departure,
+                    departure - time
+                );
+            }
+        }
+        Some("departures") => {
+            for (bus, departure) in schedule.departures_between(time_arg(1)?, time_arg(2)?) {
+                println!("{} bus {}", departure, bus.id);
+            }
+        }
+        Some("timetable") => print!("{}", schedule.timetable(time_arg(1)?, time_arg(2)?)),
+        Some("align") => {
+            let requirements = parse_requirements(args.get(1).ok_or("missing buses")?)?;
+            match schedule.aligned(&requirements) {
+                Ok(solution) => {
+                    println!("timestamp {}", solution.value);
+                    println!("repeats every {}", solution.period);
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
     }
+    std::process::exit(0);
 }
 
-fn part1(earliest_departure: i64, bus_ids: &Vec<&str>) -> (i64, i64) {
-    let (bus_index, wait_time) = bus_ids.iter().enumerate().filter_map(|(index, id)| if id == &"x" {
-        None
-    } else {
-        let loop_time = id.parse::<i64>().unwrap();
-        Some((index, loop_time - (earliest_departure % loop_time)))
-    }).min_by_key(|(_, wait)| *wait).unwrap();
-    (bus_ids[bus_index].parse().unwrap(), wait_time)
+// The first bus leaving at or after the earliest departure, and the wait.
+fn part1(schedule: &Schedule) -> (Bus, i64) {
+    let (bus, departure) = schedule.next_departures(schedule.earliest_departure)[0];
+    (bus, departure - schedule.earliest_departure)
 }
 
-// This will run the examples but not the real input.
-fn part2slow(bus_ids: &Vec<&str>) -> i64 {
-    let indexed_bus_ids: Vec<(usize, i64)> = bus_ids.iter().enumerate().filter_map(|(index, id)| if id == &"x" {
-        None
-    } else {
-        Some((index, id.parse::<i64>().unwrap()))
-    }).collect();
-    let (tx, rx) = channel();
-    for thread_index in 0..12 {
-        let tx = tx.clone();
-        let indexed_bus_ids = indexed_bus_ids.clone();
-        thread::spawn(move|| {
-            for multiple in ((5263157894736 + thread_index)..).step_by(12) {
-                let timestamp = indexed_bus_ids[0].1  * multiple;
-                if indexed_bus_ids[1..].iter().all(|&(index, bus_id)| (timestamp + index as i64) % bus_id == 0) {
-                    tx.send(timestamp).unwrap();
+// Brute forces part 2 in parallel, printing progress to stderr, then checks
+// the answer against the CRT solver. Fine for the examples, far too slow for
+// the real input without a --timeout.
+fn part2slow(schedule: &Schedule, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut options = SearchOptions::default();
+    let mut timeout: Option<Duration> = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--threads" => options.threads = value.parse()?,
+            "--limit" => options.limit = value.parse()?,
+            "--timeout" => timeout = Some(Duration::from_secs(value.parse()?)),
+            _ => return Err(format!("unknown option {}", flag).into()),
+        }
+    }
+    let cancel = AtomicBool::new(false);
+    let result = search(schedule.buses(), &options, &cancel, |progress| {
+        eprintln!(
+            "checked {} candidates in {:.1}s",
+            progress.checked,
+            progress.elapsed.as_secs_f64()
+        );
+        if matches!(timeout, Some(timeout) if progress.elapsed >= timeout) {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    });
+    // The CRT answer, Err when it's too big for the search to ever reach.
+    let expected = part2(schedule)
+        .ok()
+        .map(|solution| i64::try_from(solution.value).map_err(|_| solution.value));
+    match result {
+        SearchResult::Found(timestamp) => {
+            println!("timestamp {}", timestamp);
+            match expected {
+                Some(Ok(expected)) if expected == timestamp => println!("matches the CRT solver"),
+                Some(Ok(expected)) => println!("the CRT solver disagrees: {}", expected),
+                Some(Err(value)) => println!("the CRT solver disagrees: {}", value),
+                None => println!("the CRT solver disagrees: no timestamp"),
+            }
+        }
+        SearchResult::NotFound => {
+            println!("no timestamp below {}", options.limit);
+            match expected {
+                Some(Ok(expected)) if expected < options.limit => {
+                    println!("the CRT solver disagrees: {}", expected)
                 }
+                Some(Err(value)) => println!(
+                    "the CRT answer {} is out of the search's range, which ends at {}",
+                    value,
+                    i64::MAX
+                ),
+                _ => (),
             }
-        });
+        }
+        SearchResult::Cancelled => println!("search cancelled"),
     }
-    rx.recv().unwrap()
+    Ok(())
 }
 
-// Kinda cheating but whatever
-fn part2(bus_ids: &Vec<&str>) -> i64 {
-    let indexed_bus_ids: Vec<(i64, i64)> = bus_ids.iter().enumerate().filter_map(|(index, id)| if id == &"x" {
-        None
-    } else {
-        Some((index as i64, id.parse::<i64>().unwrap()))
-    }).collect();
-    let mut u = Vec::new();
-    let mut m = Vec::new();
-    for (index, bus_id) in indexed_bus_ids {
-        u.push(-index);
-        m.push(bus_id);
+// Bus `id` at offset `index` has to leave at t + index, so t ≡ -index (mod id).
+// Ids don't need to be coprime, clashing ones are reported as an error.
+fn part2(schedule: &Schedule) -> Result<Solution, AlignError> {
+    let requirements: Vec<(i64, i64)> = schedule
+        .buses()
+        .iter()
+        .map(|bus| (bus.id, bus.offset))
+        .collect();
+    schedule.aligned(&requirements)
+}
+
+mod test {
+    fn schedule(bus_list: &str) -> super::Schedule {
+        let input = format!("939\n{}", bus_list);
+        super::Schedule::from_lines(input.lines().map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn example_part1() {
+        let (bus, wait) = super::part1(&schedule("7,13,x,x,59,x,31,19"));
+        assert_eq!((bus.id, wait), (59, 5));
+    }
+
+    #[test]
+    fn example_part2() {
+        let examples = [
+            ("7,13,x,x,59,x,31,19", 1068781),
+            ("17,x,13,19", 3417),
+            ("67,7,59,61", 754018),
+            ("67,x,7,59,61", 779210),
+            ("67,7,x,59,61", 1261476),
+            ("1789,37,47,1889", 1202161486),
+        ];
+        for &(bus_list, timestamp) in examples.iter() {
+            let schedule = schedule(bus_list);
+            let solution = super::part2(&schedule).unwrap();
+            assert_eq!(solution.value, timestamp);
+            let period: i128 = schedule.buses().iter().map(|bus| bus.id as i128).product();
+            assert_eq!(solution.period, period);
+        }
+
+        // 4 and 6 share a factor of 2, the odd gap between them can't work.
+        assert_eq!(
+            super::part2(&schedule("4,6")),
+            Err(super::AlignError::Clash { id: 6, offset: 1 })
+        );
+        assert_eq!(
+            super::part2(&schedule("4,x,6")).unwrap(),
+            super::Solution {
+                value: 4,
+                period: 12
+            }
+        );
     }
-    chinese_remainder_theorem(&u, &m).unwrap()
 }
@@ -0,0 +1,266 @@
+use crate::crt::{self, Congruence, CrtError, Solution};
+use std::{
+    error::Error,
+    fmt::{self, Display, Write},
+};
+
+// A bus loops every `id` minutes, leaving at every multiple of its id.
+// `offset` is its position in the input's list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bus {
+    pub id: i64,
+    pub offset: i64,
+}
+
+impl Bus {
+    // The first time this bus leaves at or after `time`.
+    pub fn next_departure(&self, time: i64) -> i64 {
+        time + (-time).rem_euclid(self.id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    MissingLine(&'static str),
+    BadDeparture(String),
+    BadBusId { offset: usize, text: String },
+    NoBuses,
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::MissingLine(line) => write!(f, "the {} line is missing", line),
+            ScheduleError::BadDeparture(text) => {
+                write!(f, "\"{}\" is not an earliest departure time", text)
+            }
+            ScheduleError::BadBusId { offset, text } => write!(
+                f,
+                "bus {} in the list is \"{}\", expected x or a positive id",
+                offset, text
+            ),
+            ScheduleError::NoBuses => write!(f, "the schedule has no buses in service"),
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlignError {
+    UnknownBus(i64),
+    // Bus `id` can never leave `offset` minutes after the start while the
+    // buses asked for before it leave at theirs.
+    Clash { id: i64, offset: i64 },
+    Overflow,
+}
+
+impl Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignError::UnknownBus(id) => write!(f, "bus {} is not in the schedule", id),
+            AlignError::Clash { id, offset } => write!(
+                f,
+                "no time works, bus {} at offset {} can never line up with the buses before it",
+                id, offset
+            ),
+            AlignError::Overflow => write!(f, "the buses' combined period is too large"),
+        }
+    }
+}
+
+impl Error for AlignError {}
+
+pub struct Schedule {
+    pub earliest_departure: i64,
+    buses: Vec<Bus>,
+}
+
+impl Schedule {
+    // The first line is the earliest departure, the second the comma separated
+    // bus list with x for buses out of service.
+    pub fn from_lines(mut lines: impl Iterator<Item = String>) -> Result<Self, ScheduleError> {
+        let departure = lines
+            .next()
+            .ok_or(ScheduleError::MissingLine("earliest departure"))?;
+        let earliest_departure = departure
+            .trim()
+            .parse()
+            .map_err(|_| ScheduleError::BadDeparture(departure.clone()))?;
+        let bus_list = lines.next().ok_or(ScheduleError::MissingLine("bus list"))?;
+        let mut buses = Vec::new();
+        for (offset, text) in bus_list.trim().split(',').enumerate() {
+            if text == "x" {
+                continue;
+            }
+            match text.parse::<i64>() {
+                Ok(id) if id > 0 => buses.push(Bus {
+                    id,
+                    offset: offset as i64,
+                }),
+                _ => {
+                    return Err(ScheduleError::BadBusId {
+                        offset,
+                        text: String::from(text),
+                    })
+                }
+            }
+        }
+        if buses.is_empty() {
+            return Err(ScheduleError::NoBuses);
+        }
+        Ok(Schedule {
+            earliest_departure,
+            buses,
+        })
+    }
+
+    pub fn buses(&self) -> &[Bus] {
+        &self.buses
+    }
+
+    pub fn bus(&self, id: i64) -> Option<Bus> {
+        self.buses.iter().copied().find(|bus| bus.id == id)
+    }
+
+    // Each bus with the first time it leaves at or after `time`, soonest
+    // first. Buses leaving together stay in list order.
+    pub fn next_departures(&self, time: i64) -> Vec<(Bus, i64)> {
+        let mut departures: Vec<(Bus, i64)> = self
+            .buses
+            .iter()
+            .map(|&bus| (bus, bus.next_departure(time)))
+            .collect();
+        departures.sort_by_key(|&(_, departure)| departure);
+        departures
+    }
+
+    // Every departure in [start, end) in time order.
+    pub fn departures_between(&self, start: i64, end: i64) -> Vec<(Bus, i64)> {
+        let mut departures = Vec::new();
+        for &bus in self.buses.iter() {
+            let mut departure = bus.next_departure(start);
+            while departure < end {
+                departures.push((bus, departure));
+                departure += bus.id;
+            }
+        }
+        departures.sort_by_key(|&(bus, departure)| (departure, bus.offset));
+        departures
+    }
+
+    // The first time t, not before 0, at which every listed bus id leaves at
+    // t + its offset, along with how often that happens.
+    pub fn aligned(&self, requirements: &[(i64, i64)]) -> Result<Solution, AlignError> {
+        let mut congruences = Vec::with_capacity(requirements.len());
+        for &(id, offset) in requirements.iter() {
+            let bus = self.bus(id).ok_or(AlignError::UnknownBus(id))?;
+            congruences.push(Congruence {
+                residue: -offset as i128,
+                modulus: bus.id as i128,
+            });
+        }
+        crt::solve(&congruences).map_err(|e| match e {
+            CrtError::NoSolution { index } => AlignError::Clash {
+                id: requirements[index].0,
+                offset: requirements[index].1,
+            },
+            _ => AlignError::Overflow,
+        })
+    }
+
+    // One row per minute in [start, end) with a D under every bus leaving
+    // then, like the puzzle's own table.
+    pub fn timetable(&self, start: i64, end: i64) -> String {
+        let mut out = String::new();
+        write!(out, "{:<10}", "time").unwrap();
+        for bus in self.buses.iter() {
+            write!(out, "{:>8}", format!("bus {}", bus.id)).unwrap();
+        }
+        out.push('\n');
+        for time in start..end {
+            write!(out, "{:<10}", time).unwrap();
+            for bus in self.buses.iter() {
+                let mark = if time.rem_euclid(bus.id) == 0 {
+                    "D"
+                } else {
+                    "."
+                };
+                write!(out, "{:>8}", mark).unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+mod test {
+    const EXAMPLE: &str = "939\n7,13,x,x,59,x,31,19";
+
+    fn example() -> super::Schedule {
+        super::Schedule::from_lines(EXAMPLE.lines().map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn departures() {
+        use super::Bus;
+        let schedule = example();
+        let next = schedule.next_departures(939);
+        assert_eq!(next[0], (Bus { id: 59, offset: 4 }, 944));
+        assert_eq!(next.len(), 5);
+        assert_eq!(Bus { id: 7, offset: 0 }.next_departure(938), 938);
+        let window: Vec<(i64, i64)> = schedule
+            .departures_between(938, 945)
+            .into_iter()
+            .map(|(bus, time)| (bus.id, time))
+            .collect();
+        assert_eq!(window, vec![(7, 938), (59, 944)]);
+        assert_eq!(
+            schedule.timetable(944, 946),
+            "time         bus 7  bus 13  bus 59  bus 31  bus 19\n\
+             944              .       .       D       .       .\n\
+             945              D       .       .       .       .\n"
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        use super::{AlignError, Solution};
+        let schedule = example();
+        assert_eq!(
+            schedule.aligned(&[(17, 0)]),
+            Err(AlignError::UnknownBus(17))
+        );
+        assert_eq!(
+            schedule.aligned(&[(7, 0), (13, 1)]),
+            Ok(Solution {
+                value: 77,
+                period: 91
+            })
+        );
+        let schedule = super::Schedule::from_lines("0\n4,6".lines().map(String::from)).unwrap();
+        assert_eq!(
+            schedule.aligned(&[(4, 0), (6, 1)]),
+            Err(AlignError::Clash { id: 6, offset: 1 })
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        use super::{Schedule, ScheduleError};
+        let parse = |input: &str| Schedule::from_lines(input.lines().map(String::from)).err();
+        assert_eq!(parse("939"), Some(ScheduleError::MissingLine("bus list")));
+        assert_eq!(
+            parse("soon\n7"),
+            Some(ScheduleError::BadDeparture(String::from("soon")))
+        );
+        assert_eq!(
+            parse("939\n7,x,0"),
+            Some(ScheduleError::BadBusId {
+                offset: 2,
+                text: String::from("0")
+            })
+        );
+        assert_eq!(parse("939\nx,x"), Some(ScheduleError::NoBuses));
+    }
+}
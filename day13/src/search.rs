@@ -0,0 +1,217 @@
+use crate::schedule::Bus;
+use std::{
+    sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How many candidates a worker checks between looking at the shared state.
+const BLOCK: i64 = 1 << 14;
+
+pub struct SearchOptions {
+    pub threads: usize,
+    // Only timestamps below this are tried.
+    pub limit: i64,
+    pub progress_interval: Duration,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            limit: i64::MAX,
+            progress_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub checked: u64,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchResult {
+    Found(i64),
+    NotFound,
+    Cancelled,
+}
+
+// Brute force search for the first timestamp t where every bus leaves at
+// t + its offset, slow but with nothing clever to get wrong, which makes it a
+// cross-check for the CRT solver. Only timestamps where the longest looping
+// bus lines up are tried, and the workers take interleaved blocks of those.
+// Once a worker finds a match everyone stops as soon as they've passed it, so
+// the smallest match always wins. `progress` is called on the calling thread
+// every `progress_interval`, and setting `cancel` stops the search early. A
+// match is only reported if cancelling didn't leave anything below it unchecked.
+pub fn search(
+    buses: &[Bus],
+    options: &SearchOptions,
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(Progress),
+) -> SearchResult {
+    let sieve = match buses.iter().max_by_key(|bus| bus.id) {
+        Some(&bus) => bus,
+        None => return SearchResult::Found(0),
+    };
+    // Candidates are sieve.id * k - sieve.offset, starting from the first one
+    // that isn't negative.
+    let first_k = (sieve.offset + sieve.id - 1).div_euclid(sieve.id);
+    let threads = options.threads.max(1) as i64;
+    let best = AtomicI64::new(i64::MAX);
+    // The smallest candidate a worker gave up on when cancelled.
+    let abandoned = AtomicI64::new(i64::MAX);
+    let checked = AtomicU64::new(0);
+    let running = AtomicU64::new(threads as u64);
+    let start = Instant::now();
+
+    let worker = |index: i64| {
+        let mut block = index;
+        'blocks: loop {
+            let block_start = first_k + block * BLOCK;
+            for k in block_start..block_start + BLOCK {
+                let timestamp = match sieve.id.checked_mul(k) {
+                    Some(t) if t - sieve.offset < options.limit => t - sieve.offset,
+                    _ => break 'blocks,
+                };
+                if timestamp >= best.load(Ordering::Relaxed) {
+                    break 'blocks;
+                }
+                if buses
+                    .iter()
+                    .all(|bus| (timestamp + bus.offset) % bus.id == 0)
+                {
+                    best.fetch_min(timestamp, Ordering::Relaxed);
+                    break 'blocks;
+                }
+            }
+            checked.fetch_add(BLOCK as u64, Ordering::Relaxed);
+            block += threads;
+            if cancel.load(Ordering::Relaxed) {
+                if let Some(t) = sieve.id.checked_mul(first_k + block * BLOCK) {
+                    if t - sieve.offset < options.limit {
+                        abandoned.fetch_min(t - sieve.offset, Ordering::Relaxed);
+                    }
+                }
+                break;
+            }
+        }
+        running.fetch_sub(1, Ordering::Release);
+    };
+
+    thread::scope(|scope| {
+        for index in 0..threads {
+            let worker = &worker;
+            scope.spawn(move || worker(index));
+        }
+        let mut last_report = Instant::now();
+        while running.load(Ordering::Acquire) > 0 {
+            thread::sleep(Duration::from_millis(10).min(options.progress_interval));
+            if last_report.elapsed() >= options.progress_interval {
+                last_report = Instant::now();
+                progress(Progress {
+                    checked: checked.load(Ordering::Relaxed),
+                    elapsed: start.elapsed(),
+                });
+            }
+        }
+    });
+
+    let best = best.load(Ordering::Relaxed);
+    let abandoned = abandoned.load(Ordering::Relaxed);
+    if best < abandoned {
+        SearchResult::Found(best)
+    } else if abandoned < i64::MAX {
+        SearchResult::Cancelled
+    } else {
+        SearchResult::NotFound
+    }
+}
+
+mod test {
+    fn schedule(bus_list: &str) -> crate::schedule::Schedule {
+        let input = format!("0\n{}", bus_list);
+        crate::schedule::Schedule::from_lines(input.lines().map(String::from)).unwrap()
+    }
+
+    #[test]
+    fn matches_crt() {
+        use super::{search, SearchOptions, SearchResult};
+        use std::sync::atomic::AtomicBool;
+        let cancel = AtomicBool::new(false);
+        for &bus_list in ["7,13,x,x,59,x,31,19", "17,x,13,19", "67,7,x,59,61", "4,x,6"].iter() {
+            let schedule = schedule(bus_list);
+            let requirements: Vec<(i64, i64)> =
+                schedule.buses().iter().map(|b| (b.id, b.offset)).collect();
+            let expected = schedule.aligned(&requirements).unwrap().value as i64;
+            for threads in 1..4 {
+                let options = SearchOptions {
+                    threads,
+                    ..SearchOptions::default()
+                };
+                assert_eq!(
+                    search(schedule.buses(), &options, &cancel, |_| ()),
+                    SearchResult::Found(expected)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn limit_and_cancel() {
+        use super::{search, SearchOptions, SearchResult};
+        use std::sync::atomic::AtomicBool;
+        let buses = schedule("4,6").buses().to_vec();
+        let options = SearchOptions {
+            threads: 2,
+            limit: 1000,
+            ..SearchOptions::default()
+        };
+        let cancel = AtomicBool::new(false);
+        assert_eq!(
+            search(&buses, &options, &cancel, |_| ()),
+            SearchResult::NotFound
+        );
+        let cancel = AtomicBool::new(true);
+        let options = SearchOptions {
+            threads: 2,
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            search(&buses, &options, &cancel, |_| ()),
+            SearchResult::Cancelled
+        );
+    }
+
+    #[test]
+    fn cancel_keeps_smallest() {
+        use super::{search, SearchOptions, SearchResult};
+        use std::sync::atomic::AtomicBool;
+        // Cancelled from the start, each worker checks its first block only.
+        let cancel = AtomicBool::new(true);
+        let options = |threads| SearchOptions {
+            threads,
+            ..SearchOptions::default()
+        };
+        // The answer is in the second block, so nothing below it is skipped.
+        let buses = schedule("40037,40039").buses().to_vec();
+        assert_eq!(
+            search(&buses, &options(2), &cancel, |_| ()),
+            SearchResult::Found(801540740)
+        );
+        // The answer is in the third block, which only a third worker reaches.
+        let buses = schedule("40087,40093").buses().to_vec();
+        assert_eq!(
+            search(&buses, &options(2), &cancel, |_| ()),
+            SearchResult::Cancelled
+        );
+        assert_eq!(
+            search(&buses, &options(3), &cancel, |_| ()),
+            SearchResult::Found(1339346757)
+        );
+    }
+}
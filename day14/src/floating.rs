@@ -0,0 +1,185 @@
+use crate::mask::{Mask, ADDRESS_SPACE};
+
+// Every address matching `fixed` on the bits not in `floating`, the floating
+// bits of `fixed` are always 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AddressPattern {
+    pub fixed: i64,
+    pub floating: i64,
+}
+
+impl AddressPattern {
+    // The addresses a version 2 write to `address` under `mask` touches.
+    pub fn new(address: i64, mask: &Mask) -> Self {
+        let floating = mask
+            .floating
+            .iter()
+            .fold(0, |bits, offset| bits | 1 << offset);
+        AddressPattern {
+            fixed: (address | mask.ones) & !floating,
+            floating,
+        }
+    }
+
+    pub fn len(&self) -> i64 {
+        1 << self.floating.count_ones()
+    }
+
+    pub fn contains(&self, address: i64) -> bool {
+        (address & !self.floating) == self.fixed
+    }
+
+    // Two patterns share an address unless some bit is fixed differently.
+    pub fn intersects(&self, other: &AddressPattern) -> bool {
+        let fixed_in_both = !(self.floating | other.floating) & ADDRESS_SPACE;
+        (self.fixed ^ other.fixed) & fixed_in_both == 0
+    }
+
+    // Splits the addresses in self but not in other into disjoint patterns.
+    // Each bit floating here but fixed in other peels off the half that
+    // disagrees with other, then is pinned to other's value before moving on
+    // to the next. Whatever is left at the end lies inside other.
+    pub fn subtract(&self, other: &AddressPattern) -> Vec<AddressPattern> {
+        if !self.intersects(other) {
+            return vec![*self];
+        }
+        let mut pieces = Vec::new();
+        let mut remaining = *self;
+        let only_floating_here = self.floating & !other.floating;
+        for offset in 0..64 {
+            let bit = 1 << offset;
+            if only_floating_here & bit == 0 {
+                continue;
+            }
+            let other_bit = other.fixed & bit;
+            remaining.floating &= !bit;
+            pieces.push(AddressPattern {
+                fixed: remaining.fixed | (other_bit ^ bit),
+                floating: remaining.floating,
+            });
+            remaining.fixed |= other_bit;
+        }
+        pieces
+    }
+}
+
+// Version 2 memory kept as disjoint address patterns and the value written to
+// each, so a write costs time in the number of patterns it overlaps rather
+// than 2^(number of X bits). A new write carves its addresses out of every
+// older pattern it overlaps before being stored whole.
+#[derive(Default)]
+pub struct FloatingMemory {
+    regions: Vec<(AddressPattern, i64)>,
+}
+
+impl FloatingMemory {
+    pub fn new() -> Self {
+        FloatingMemory::default()
+    }
+
+    pub fn write(&mut self, pattern: AddressPattern, value: i64) {
+        let mut regions = Vec::with_capacity(self.regions.len() + 1);
+        for &(region, region_value) in self.regions.iter() {
+            for piece in region.subtract(&pattern) {
+                regions.push((piece, region_value));
+            }
+        }
+        regions.push((pattern, value));
+        self.regions = regions;
+    }
+
+    pub fn get(&self, address: i64) -> i64 {
+        self.regions
+            .iter()
+            .find(|(pattern, _)| pattern.contains(address))
+            .map_or(0, |&(_, value)| value)
+    }
+
+    // Up to 2^36 addresses each holding up to 2^36 - 1, too much for an i64.
+    pub fn sum(&self) -> i128 {
+        self.regions
+            .iter()
+            .map(|(pattern, value)| pattern.len() as i128 * *value as i128)
+            .sum()
+    }
+
+    pub fn region_count(&self) -> usize {
+        self.regions.len()
+    }
+}
+
+mod test {
+    #[test]
+    fn subtraction() {
+        use super::AddressPattern;
+        // 0XX1 minus 01X1 leaves 00X1.
+        let a = AddressPattern {
+            fixed: 0b0001,
+            floating: 0b0110,
+        };
+        let b = AddressPattern {
+            fixed: 0b0101,
+            floating: 0b0010,
+        };
+        assert!(a.intersects(&b));
+        assert_eq!(
+            a.subtract(&b),
+            vec![AddressPattern {
+                fixed: 0b0001,
+                floating: 0b0010
+            }]
+        );
+        // Nothing of b is left once a floats over every bit b has fixed.
+        let c = AddressPattern {
+            fixed: 0,
+            floating: 0b1111,
+        };
+        assert_eq!(b.subtract(&c), vec![]);
+        let pieces = c.subtract(&b);
+        assert_eq!(pieces.iter().map(|p| p.len()).sum::<i64>(), 16 - 2);
+        assert!(pieces.iter().all(|p| !p.intersects(&b)));
+    }
+
+    #[test]
+    fn matches_expansion() {
+        use super::{AddressPattern, FloatingMemory};
+        use crate::lcg::Lcg;
+        use crate::mask::Mask;
+        use std::collections::HashMap;
+        // A fixed pseudo random program of overlapping writes.
+        let mut lcg = Lcg::new(12345);
+        let mut expanded: HashMap<i64, i64> = HashMap::new();
+        let mut memory = FloatingMemory::new();
+        for _ in 0..200 {
+            let mask: String = (0..36)
+                .map(|i| match (i < 26, lcg.next(3)) {
+                    (true, _) => '0',
+                    (false, 0) => '0',
+                    (false, 1) => '1',
+                    _ => 'X',
+                })
+                .collect();
+            let mask = Mask::from_str(&mask).unwrap();
+            let (address, value) = (lcg.next(1024) as i64, lcg.next(1000) as i64);
+            for a in mask.mask_v2(address) {
+                expanded.insert(a, value);
+            }
+            memory.write(AddressPattern::new(address, &mask), value);
+        }
+        assert_eq!(memory.sum(), expanded.values().sum::<i64>() as i128);
+        for (&address, &value) in expanded.iter() {
+            assert_eq!(memory.get(address), value);
+        }
+    }
+
+    #[test]
+    fn huge_sum() {
+        use super::{AddressPattern, FloatingMemory};
+        use crate::mask::{Mask, ADDRESS_SPACE};
+        // Every address holding the largest value overflows an i64.
+        let mask = Mask::from_str(&"X".repeat(36)).unwrap();
+        let mut memory = FloatingMemory::new();
+        memory.write(AddressPattern::new(0, &mask), ADDRESS_SPACE);
+        assert_eq!(memory.sum(), (1i128 << 36) * ADDRESS_SPACE as i128);
+    }
+}
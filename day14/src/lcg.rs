@@ -0,0 +1,20 @@
+// A small fixed seed pseudo random generator, so generated test and bench
+// programs come out the same on every run.
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg { state: seed }
+    }
+
+    // The next number below `bound`.
+    pub fn next(&mut self, bound: u64) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.state >> 33) % bound
+    }
+}
@@ -1,102 +1,197 @@
-use std::{collections::HashMap, env, fs::File, io::{self, BufRead}, path::Path};
-use regex::Regex;
+#![allow(dead_code)]
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+    time::Instant,
+};
 
-#[derive(Default)]
-struct Mask {
-    zeros: i64,
-    ones: i64,
-    floating: Vec<i32>,
-}
-
-impl Mask {
-    fn update(&mut self, s: &str) {
-        self.zeros = 0b111111111111111111111111111111111111;
-        self.ones = 0;
-        self.floating.clear();
-        for (offset, c) in s.split(" = ").skip(1).next().unwrap().chars().rev().enumerate() {
-            match c {
-                '0' => self.zeros &= !(1 << offset),
-                '1' => self.ones |= 1 << offset,
-                'X' => self.floating.push(offset as i32),
-                _ => continue,
-            }
-        }
-    }
+mod floating;
+mod lcg;
+mod mask;
+mod program;
 
-    fn mask(&self, number: i64) -> i64 {
-        let number = number | self.ones;
-        let number = number & self.zeros;
-        number
-    }
+use floating::{AddressPattern, FloatingMemory};
+use lcg::Lcg;
+use mask::Mask;
+use program::{parse_program, DockingInstruction};
 
-    fn mask_v2(&self, number: i64) -> Vec<i64> {
-        let base_address = number | self.ones;
-        let num_perms = usize::pow(2, self.floating.len() as u32);
-        let mut addresses: Vec<i64> = vec![base_address; num_perms];
-        for permutation in  0..num_perms {
-            for (bit_number, offset) in self.floating.iter().enumerate() {
-                match 1 & permutation >> bit_number {
-                    1 => addresses[permutation] |= 1 << offset,
-                    0 => addresses[permutation] &= !(1 << offset),
-                    _ => panic!("this is news to me"),
-                }
-            }
-        }
-        addresses
-    }
-}
-
-fn main() -> Result<(), io::Error> {
+fn main() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
-    let lines: Vec<String> = io::BufReader::new(input_file).lines().map(|l| l.unwrap()).collect();
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        println!("{}", part1(&lines));
+    let lines = io::BufReader::new(input_file).lines().map(|l| l.unwrap());
+    let program = match parse_program(lines) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if env::args().nth(1) == Some(String::from("part1")) {
+        println!("{}", part1(&program));
+        std::process::exit(0);
+    } else if env::args().nth(1) == Some(String::from("part2")) {
+        println!("{}", part2(&program));
         std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        println!("{}", part2(&lines));
+    } else if env::args().nth(1) == Some(String::from("bench")) {
+        let iterations = env::args()
+            .nth(2)
+            .map(|i| i.parse::<u32>())
+            .transpose()?
+            .unwrap_or(10);
+        bench(&program, iterations.max(1));
         std::process::exit(0);
     } else {
         println!("usage: cargo run (part1 | part2)");
+        println!("       cargo run --release bench [iterations]");
         std::process::exit(1);
     }
 }
 
-fn part1(code: &Vec<String>) -> i64 {
-    let assignment_regex = Regex::new(r"mem\[(\d+)\] = (\d+)").unwrap();
-    let mut ram: Vec<i64> = vec![0; usize::pow(2,16)];
+// Version 1 masks the values written.
+fn part1(program: &[DockingInstruction]) -> i64 {
+    let mut ram: HashMap<i64, i64> = HashMap::new();
+    let mut mask = Mask::default();
+    for instruction in program {
+        match instruction {
+            DockingInstruction::SetMask(new_mask) => mask = new_mask.clone(),
+            DockingInstruction::Write { addr, value } => {
+                ram.insert(*addr, mask.mask(*value));
+            }
+        }
+    }
+    ram.values().sum()
+}
+
+// Version 2 masks the addresses written to, X bits take every value.
+fn part2(program: &[DockingInstruction]) -> i128 {
+    let mut memory = FloatingMemory::new();
     let mut mask = Mask::default();
-    for line in code {
-        if &line[..4] == "mask" {
-            mask.update(line);
-        } else {
-            if let Some(cap) = assignment_regex.captures(line) {
-                let address: usize = cap.get(1).unwrap().as_str().parse().unwrap();
-                let original_value = cap.get(2).unwrap().as_str().parse().unwrap();
-                let masked_value = mask.mask(original_value);
-                ram[address] = masked_value;
+    for instruction in program {
+        match instruction {
+            DockingInstruction::SetMask(new_mask) => mask = new_mask.clone(),
+            DockingInstruction::Write { addr, value } => {
+                memory.write(AddressPattern::new(*addr, &mask), *value)
             }
         }
     }
-    ram.iter().sum()
+    memory.sum()
 }
 
-fn part2(code: &Vec<String>) -> i64 {
-    let assignment_regex = Regex::new(r"mem\[(\d+)\] = (\d+)").unwrap();
+// The original version 2 decoder, writing every address a mask expands to.
+fn part2_expanded(program: &[DockingInstruction]) -> i128 {
     let mut ram: HashMap<i64, i64> = HashMap::new();
     let mut mask = Mask::default();
-    for line in code {
-        if &line[..4] == "mask" {
-            mask.update(line);
-        } else {
-            if let Some(cap) = assignment_regex.captures(line) {
-                let original_address = cap.get(1).unwrap().as_str().parse().unwrap();
-                let value = cap.get(2).unwrap().as_str().parse().unwrap();
-                for address in mask.mask_v2(original_address).iter() {
-                    ram.insert(*address, value);
+    for instruction in program {
+        match instruction {
+            DockingInstruction::SetMask(new_mask) => mask = new_mask.clone(),
+            DockingInstruction::Write { addr, value } => {
+                for address in mask.mask_v2(*addr).iter() {
+                    ram.insert(*address, *value);
                 }
             }
         }
     }
-    ram.values().sum()
+    ram.values().map(|&value| value as i128).sum()
+}
+
+// A program of `writes` writes whose masks all have `floating` X bits, for
+// seeing how each decoder copes as the X count grows.
+fn floating_heavy_program(writes: usize, floating: usize) -> Vec<DockingInstruction> {
+    let mut lcg = Lcg::new(2020);
+    let mut program = Vec::new();
+    for _ in 0..writes {
+        let mask: String = (0..mask::MASK_LENGTH)
+            .map(|i| {
+                if i >= mask::MASK_LENGTH - floating {
+                    'X'
+                } else if lcg.next(2) == 0 {
+                    '0'
+                } else {
+                    '1'
+                }
+            })
+            .collect();
+        program.push(DockingInstruction::SetMask(Mask::from_str(&mask).unwrap()));
+        program.push(DockingInstruction::Write {
+            addr: lcg.next(1 << 20) as i64,
+            value: lcg.next(1000) as i64,
+        });
+    }
+    program
+}
+
+// Times the expanding decoder against the floating pattern one on the input
+// and on programs with more and more X bits, run with --release for
+// meaningful numbers.
+fn bench(program: &[DockingInstruction], iterations: u32) {
+    let mut cases = vec![(String::from("input"), program.to_vec())];
+    for &floating in [8, 12, 16, 18].iter() {
+        cases.push((
+            format!("{} X bits", floating),
+            floating_heavy_program(40, floating),
+        ));
+    }
+    for (name, program) in cases.iter() {
+        let start = Instant::now();
+        let mut expanded_sum = 0;
+        for _ in 0..iterations {
+            expanded_sum = part2_expanded(program);
+        }
+        let expanded_time = start.elapsed() / iterations;
+        let start = Instant::now();
+        let mut floating_sum = 0;
+        for _ in 0..iterations {
+            floating_sum = part2(program);
+        }
+        let floating_time = start.elapsed() / iterations;
+        assert_eq!(expanded_sum, floating_sum);
+        println!(
+            "{:<12} expanded {:>12?}  floating {:>12?} ({:.1}x)",
+            name,
+            expanded_time,
+            floating_time,
+            expanded_time.as_secs_f64() / floating_time.as_secs_f64()
+        );
+    }
+}
+
+mod test {
+    #[test]
+    fn example_part1() {
+        use super::{parse_program, part1};
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
+mem[8] = 11
+mem[7] = 101
+mem[8] = 0";
+        let program = parse_program(input.lines().map(String::from)).unwrap();
+        assert_eq!(part1(&program), 165);
+    }
+
+    #[test]
+    fn example_part2() {
+        use super::{parse_program, part2};
+        let input = include_str!("../test_input.txt");
+        let program = parse_program(input.lines().map(String::from)).unwrap();
+        assert_eq!(part2(&program), 208);
+    }
+
+    #[test]
+    fn write_before_mask() {
+        use super::{parse_program, part1, part2, part2_expanded};
+        let program = parse_program("mem[1] = 5".lines().map(String::from)).unwrap();
+        assert_eq!(part1(&program), 5);
+        assert_eq!(part2(&program), 5);
+        assert_eq!(part2_expanded(&program), 5);
+    }
+
+    #[test]
+    fn decoders_agree() {
+        use super::{floating_heavy_program, part2, part2_expanded};
+        let program = floating_heavy_program(30, 10);
+        assert_eq!(part2(&program), part2_expanded(&program));
+    }
 }
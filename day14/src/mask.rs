@@ -0,0 +1,86 @@
+pub const MASK_LENGTH: usize = 36;
+pub const ADDRESS_SPACE: i64 = (1 << MASK_LENGTH) - 1;
+
+// Bit i of the mask is the ith character from the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    // Every bit set except the mask's 0 bits.
+    pub zeros: i64,
+    // Only the mask's 1 bits are set.
+    pub ones: i64,
+    // Offsets of the X bits, lowest first.
+    pub floating: Vec<i32>,
+}
+
+// The mask in force before the first mask instruction, with no 0, 1 or X bits
+// so it leaves both values and addresses alone.
+impl Default for Mask {
+    fn default() -> Self {
+        Mask {
+            zeros: ADDRESS_SPACE,
+            ones: 0,
+            floating: Vec::new(),
+        }
+    }
+}
+
+impl Mask {
+    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+        if s.chars().count() != MASK_LENGTH {
+            return Err("mask must be 36 characters long");
+        }
+        let mut mask = Mask {
+            zeros: ADDRESS_SPACE,
+            ones: 0,
+            floating: Vec::new(),
+        };
+        for (offset, c) in s.chars().rev().enumerate() {
+            match c {
+                '0' => mask.zeros &= !(1 << offset),
+                '1' => mask.ones |= 1 << offset,
+                'X' => mask.floating.push(offset as i32),
+                _ => return Err("mask may only contain 0, 1 and X"),
+            }
+        }
+        Ok(mask)
+    }
+
+    pub fn mask(&self, number: i64) -> i64 {
+        let number = number | self.ones;
+        number & self.zeros
+    }
+
+    pub fn mask_v2(&self, number: i64) -> Vec<i64> {
+        let base_address = number | self.ones;
+        let num_perms = usize::pow(2, self.floating.len() as u32);
+        let mut addresses: Vec<i64> = vec![base_address; num_perms];
+        for (permutation, address) in addresses.iter_mut().enumerate() {
+            for (bit_number, offset) in self.floating.iter().enumerate() {
+                match 1 & permutation >> bit_number {
+                    1 => *address |= 1 << offset,
+                    _ => *address &= !(1 << offset),
+                }
+            }
+        }
+        addresses
+    }
+}
+
+mod test {
+    #[test]
+    fn parse_and_apply() {
+        use super::Mask;
+        let mask = Mask::from_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").unwrap();
+        assert_eq!((mask.mask(11), mask.mask(101), mask.mask(0)), (73, 101, 64));
+        assert_eq!(
+            Mask::from_str("X1001X"),
+            Err("mask must be 36 characters long")
+        );
+        assert_eq!(
+            Mask::from_str("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX2X"),
+            Err("mask may only contain 0, 1 and X")
+        );
+        let mask = Mask::from_str("000000000000000000000000000000X1001X").unwrap();
+        assert_eq!(mask.mask_v2(42), vec![26, 27, 58, 59]);
+    }
+}
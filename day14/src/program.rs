@@ -0,0 +1,105 @@
+use crate::mask::{Mask, ADDRESS_SPACE};
+use regex::Regex;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockingInstruction {
+    SetMask(Mask),
+    Write { addr: i64, value: i64 },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} \"{}\"", self.line, self.reason, self.text)
+    }
+}
+
+impl Error for ParseError {}
+
+// Parses `mask = ...` and `mem[addr] = value` lines. Blank lines are skipped
+// but still counted so line numbers match the file.
+pub fn parse_program(
+    lines: impl Iterator<Item = String>,
+) -> Result<Vec<DockingInstruction>, ParseError> {
+    let mask_regex = Regex::new(r"^mask = (\S*)$").unwrap();
+    let assignment_regex = Regex::new(r"^mem\[(\d+)\] = (\d+)$").unwrap();
+    let mut program = Vec::new();
+    for (index, text) in lines.enumerate() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let parsed = if let Some(cap) = mask_regex.captures(text) {
+            Mask::from_str(&cap[1]).map(DockingInstruction::SetMask)
+        } else if let Some(cap) = assignment_regex.captures(text) {
+            let within_36_bits = |s: &str| s.parse::<i64>().ok().filter(|&n| n <= ADDRESS_SPACE);
+            match (within_36_bits(&cap[1]), within_36_bits(&cap[2])) {
+                (Some(addr), Some(value)) => Ok(DockingInstruction::Write { addr, value }),
+                (None, _) => Err("address doesn't fit in 36 bits"),
+                (_, None) => Err("value doesn't fit in 36 bits"),
+            }
+        } else {
+            Err("expected mask = <mask> or mem[<address>] = <value>")
+        };
+        program.push(parsed.map_err(|reason| ParseError {
+            line: index + 1,
+            text: String::from(text),
+            reason,
+        })?);
+    }
+    Ok(program)
+}
+
+mod test {
+    #[test]
+    fn parsing() {
+        use super::{parse_program, DockingInstruction, ParseError};
+        use crate::mask::Mask;
+        let program =
+            parse_program(include_str!("../test_input.txt").lines().map(String::from)).unwrap();
+        assert_eq!(program.len(), 4);
+        assert_eq!(
+            program[0],
+            DockingInstruction::SetMask(
+                Mask::from_str("000000000000000000000000000000X1001X").unwrap()
+            )
+        );
+        assert_eq!(program[3], DockingInstruction::Write { addr: 26, value: 1 });
+
+        let error = |input: &str| {
+            parse_program(input.lines().map(String::from))
+                .err()
+                .unwrap()
+        };
+        assert_eq!(
+            error("mem[8] = 11\n\nmask = X1"),
+            ParseError {
+                line: 3,
+                text: String::from("mask = X1"),
+                reason: "mask must be 36 characters long"
+            }
+        );
+        assert_eq!(
+            error("mem[68719476736] = 1").reason,
+            "address doesn't fit in 36 bits"
+        );
+        assert_eq!(
+            error("mem[1] = 68719476736").reason,
+            "value doesn't fit in 36 bits"
+        );
+        assert_eq!(
+            error("mas").reason,
+            "expected mask = <mask> or mem[<address>] = <value>"
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use crate::solution::BagGraph;
+use std::{collections::HashSet, fmt::Write};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl Format {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "dot" => Some(Format::Dot),
+            "mermaid" => Some(Format::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subgraph {
+    Everything,
+    // Bags that can end up inside the given bag.
+    From(String),
+    // Bags that can eventually hold the given bag.
+    To(String),
+}
+
+// Returns None if the subgraph is rooted at a colour with no rule.
+pub fn export(graph: &BagGraph, format: Format, subgraph: &Subgraph) -> Option<String> {
+    let included: HashSet<usize> = match subgraph {
+        Subgraph::Everything => (0..graph.rules().len()).collect(),
+        Subgraph::From(colour) => graph.reachable_from(colour)?,
+        Subgraph::To(colour) => graph.reachable_to(colour)?,
+    };
+    Some(match format {
+        Format::Dot => to_dot(graph, &included),
+        Format::Mermaid => to_mermaid(graph, &included),
+    })
+}
+
+// Every included bag is declared as a node, edges are only drawn when both
+// ends are included and are labelled with the number of inner bags.
+fn edges<'a>(
+    graph: &'a BagGraph,
+    included: &'a HashSet<usize>,
+) -> impl Iterator<Item = (usize, u64, usize)> + 'a {
+    let rules = graph.rules();
+    (0..rules.len())
+        .filter(move |index| included.contains(index))
+        .flat_map(move |index| {
+            rules[index]
+                .contents
+                .iter()
+                .map(move |(count, inner)| (index, *count, graph.index_of(inner).unwrap()))
+        })
+        .filter(move |(_, _, inner)| included.contains(inner))
+}
+
+fn to_dot(graph: &BagGraph, included: &HashSet<usize>) -> String {
+    let rules = graph.rules();
+    let mut out = String::from("digraph bags {\n");
+    for (index, rule) in rules.iter().enumerate() {
+        if included.contains(&index) {
+            writeln!(out, "    \"{}\";", rule.colour).unwrap();
+        }
+    }
+    for (outer, count, inner) in edges(graph, included) {
+        writeln!(
+            out,
+            "    \"{}\" -> \"{}\" [label=\"{}\"];",
+            rules[outer].colour, rules[inner].colour, count
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Mermaid ids can't contain spaces so nodes are named after their rule index
+// and labelled with the colour.
+fn to_mermaid(graph: &BagGraph, included: &HashSet<usize>) -> String {
+    let rules = graph.rules();
+    let mut out = String::from("graph TD\n");
+    for (index, rule) in rules.iter().enumerate() {
+        if included.contains(&index) {
+            writeln!(out, "    bag{}[\"{}\"]", index, rule.colour).unwrap();
+        }
+    }
+    for (outer, count, inner) in edges(graph, included) {
+        writeln!(out, "    bag{} -->|{}| bag{}", outer, count, inner).unwrap();
+    }
+    out
+}
+
+mod test {
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag.
+dark olive bags contain no other bags.
+faded blue bags contain no other bags.";
+
+    #[test]
+    fn dot_subgraph_from() {
+        use super::{export, Format, Subgraph};
+        let graph =
+            crate::solution::BagGraph::from_lines(EXAMPLE.lines().map(String::from)).unwrap();
+        let subgraph = Subgraph::From(String::from("muted yellow"));
+        assert_eq!(
+            export(&graph, Format::Dot, &subgraph).unwrap(),
+            "digraph bags {
+    \"muted yellow\";
+    \"shiny gold\";
+    \"dark olive\";
+    \"faded blue\";
+    \"muted yellow\" -> \"shiny gold\" [label=\"2\"];
+    \"muted yellow\" -> \"faded blue\" [label=\"9\"];
+    \"shiny gold\" -> \"dark olive\" [label=\"1\"];
+}
+"
+        );
+    }
+
+    #[test]
+    fn mermaid_subgraph_to() {
+        use super::{export, Format, Subgraph};
+        let graph =
+            crate::solution::BagGraph::from_lines(EXAMPLE.lines().map(String::from)).unwrap();
+        let subgraph = Subgraph::To(String::from("shiny gold"));
+        assert_eq!(
+            export(&graph, Format::Mermaid, &subgraph).unwrap(),
+            "graph TD
+    bag0[\"light red\"]
+    bag1[\"bright white\"]
+    bag2[\"muted yellow\"]
+    bag3[\"shiny gold\"]
+    bag0 -->|1| bag1
+    bag0 -->|2| bag2
+    bag1 -->|1| bag3
+    bag2 -->|2| bag3
+"
+        );
+        assert_eq!(
+            export(
+                &graph,
+                Format::Mermaid,
+                &Subgraph::To(String::from("plaid magenta"))
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,55 @@
+#![allow(dead_code)]
+use std::{
+    env,
+    error::Error,
+    fs::File,
+    io::{self, BufRead},
+    path::Path,
+};
+
+mod export;
+mod solution;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let input_path = Path::new("./input.txt");
+    let input_file = File::open(input_path)?;
+    let lines = io::BufReader::new(input_file).lines().map(|l| l.unwrap());
+    let graph = match solution::BagGraph::from_lines(lines) {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if env::args().nth(1) == Some(String::from("part1")) {
+        let containers = solution::part1(&graph).ok_or("no rule for shiny gold bags")?;
+        println!("{} bag colours can contain a shiny gold bag", containers);
+        std::process::exit(0);
+    } else if env::args().nth(1) == Some(String::from("part2")) {
+        let contained = solution::part2(&graph).ok_or("no rule for shiny gold bags")?;
+        println!("Found {} bag types to hold", contained);
+        std::process::exit(0);
+    } else if env::args().nth(1) == Some(String::from("export")) {
+        let args: Vec<String> = env::args().skip(2).collect();
+        let format = args.first().and_then(|f| export::Format::from_str(f));
+        let subgraph = match (args.get(1).map(|s| s.as_str()), args.get(2)) {
+            (None, None) => Some(export::Subgraph::Everything),
+            (Some("--from"), Some(colour)) => Some(export::Subgraph::From(colour.clone())),
+            (Some("--to"), Some(colour)) => Some(export::Subgraph::To(colour.clone())),
+            _ => None,
+        };
+        if let (Some(format), Some(subgraph)) = (format, subgraph) {
+            let exported =
+                export::export(&graph, format, &subgraph).ok_or("no rule for that bag")?;
+            print!("{}", exported);
+            std::process::exit(0);
+        }
+        println!("usage: cargo run export (dot | mermaid) [--from <bag> | --to <bag>]");
+        std::process::exit(1);
+    } else {
+        println!(
+            "usage: cargo run (part1 | part2 | export (dot | mermaid) [--from <bag> | --to <bag>])"
+        );
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BagRule {
+    pub line: usize,
+    pub text: String,
+    pub colour: String,
+    pub contents: Vec<(u64, String)>,
+}
+
+impl BagRule {
+    fn from_str(line: usize, text: &str) -> Option<Self> {
+        let (colour, contents) = text.split_once(" bags contain ")?;
+        let contents = contents.strip_suffix('.')?;
+        let mut rule = BagRule {
+            line,
+            text: String::from(text),
+            colour: String::from(colour),
+            contents: Vec::new(),
+        };
+        if contents == "no other bags" {
+            return Some(rule);
+        }
+        for item in contents.split(", ") {
+            let item = item
+                .strip_suffix(" bags")
+                .or_else(|| item.strip_suffix(" bag"))?;
+            let (count, inner_colour) = item.split_once(' ')?;
+            rule.contents
+                .push((count.parse().ok()?, String::from(inner_colour)));
+        }
+        Some(rule)
+    }
+}
+
+impl Display for BagRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.text)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BagGraphError {
+    Malformed {
+        line: usize,
+        text: String,
+    },
+    DuplicateRule {
+        first: Box<BagRule>,
+        duplicate: Box<BagRule>,
+    },
+    DanglingReference {
+        rule: Box<BagRule>,
+        missing: String,
+    },
+    Cycle(Vec<BagRule>),
+}
+
+impl Display for BagGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BagGraphError::Malformed { line, text } => {
+                write!(f, "line {}: could not parse bag rule \"{}\"", line, text)
+            }
+            BagGraphError::DuplicateRule { first, duplicate } => write!(
+                f,
+                "{} bags have more than one rule\n  {}\n  {}",
+                first.colour, first, duplicate
+            ),
+            BagGraphError::DanglingReference { rule, missing } => write!(
+                f,
+                "{} bags are not defined by any rule\n  {}",
+                missing, rule
+            ),
+            BagGraphError::Cycle(rules) => {
+                write!(f, "bag rules contain a cycle")?;
+                for rule in rules {
+                    write!(f, "\n  {}", rule)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for BagGraphError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+// Rules are kept in file order so errors can point back at the lines involved,
+// `index` maps a colour to its position in `rules`.
+pub struct BagGraph {
+    rules: Vec<BagRule>,
+    index: HashMap<String, usize>,
+}
+
+impl BagGraph {
+    // Blank lines are skipped but still counted so line numbers match the file.
+    pub fn from_lines(lines: impl Iterator<Item = String>) -> Result<Self, BagGraphError> {
+        let mut rules: Vec<BagRule> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (line_number, text) in lines.enumerate() {
+            if text.is_empty() {
+                continue;
+            }
+            let rule =
+                BagRule::from_str(line_number + 1, &text).ok_or(BagGraphError::Malformed {
+                    line: line_number + 1,
+                    text: text.clone(),
+                })?;
+            if let Some(&existing) = index.get(&rule.colour) {
+                return Err(BagGraphError::DuplicateRule {
+                    first: Box::new(rules[existing].clone()),
+                    duplicate: Box::new(rule),
+                });
+            }
+            index.insert(rule.colour.clone(), rules.len());
+            rules.push(rule);
+        }
+        let graph = BagGraph { rules, index };
+        graph.check_references()?;
+        if let Some(cycle) = graph.find_cycle() {
+            return Err(BagGraphError::Cycle(
+                cycle.into_iter().map(|i| graph.rules[i].clone()).collect(),
+            ));
+        }
+        Ok(graph)
+    }
+
+    pub fn rule(&self, colour: &str) -> Option<&BagRule> {
+        self.index.get(colour).map(|&i| &self.rules[i])
+    }
+
+    pub fn index_of(&self, colour: &str) -> Option<usize> {
+        self.index.get(colour).copied()
+    }
+
+    pub fn rules(&self) -> &[BagRule] {
+        &self.rules
+    }
+
+    fn check_references(&self) -> Result<(), BagGraphError> {
+        for rule in self.rules.iter() {
+            for (_, colour) in rule.contents.iter() {
+                if !self.index.contains_key(colour) {
+                    return Err(BagGraphError::DanglingReference {
+                        rule: Box::new(rule.clone()),
+                        missing: colour.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_cycle(&self) -> Option<Vec<usize>> {
+        let mut state = vec![Visit::Unvisited; self.rules.len()];
+        let mut path: Vec<usize> = Vec::new();
+        for start in 0..self.rules.len() {
+            if let Some(cycle) = self.visit(start, &mut state, &mut path) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    fn visit(&self, node: usize, state: &mut [Visit], path: &mut Vec<usize>) -> Option<Vec<usize>> {
+        match state[node] {
+            Visit::Done => return None,
+            Visit::InProgress => {
+                let start = path.iter().position(|&n| n == node).unwrap();
+                return Some(path[start..].to_vec());
+            }
+            Visit::Unvisited => (),
+        }
+        state[node] = Visit::InProgress;
+        path.push(node);
+        for (_, colour) in self.rules[node].contents.iter() {
+            if let Some(cycle) = self.visit(self.index[colour], state, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        state[node] = Visit::Done;
+        None
+    }
+
+    // Indices of every rule whose bag can eventually hold a `colour` bag,
+    // including `colour` itself.
+    pub fn reachable_to(&self, colour: &str) -> Option<HashSet<usize>> {
+        let target = *self.index.get(colour)?;
+        let mut visited: HashSet<usize> = HashSet::new();
+        visited.insert(target);
+        let mut to_search = vec![target];
+        while let Some(current) = to_search.pop() {
+            for (index, rule) in self.rules.iter().enumerate() {
+                if !visited.contains(&index)
+                    && rule
+                        .contents
+                        .iter()
+                        .any(|(_, inner)| self.index[inner] == current)
+                {
+                    visited.insert(index);
+                    to_search.push(index);
+                }
+            }
+        }
+        Some(visited)
+    }
+
+    // Indices of every rule whose bag can end up inside a `colour` bag,
+    // including `colour` itself.
+    pub fn reachable_from(&self, colour: &str) -> Option<HashSet<usize>> {
+        let start = *self.index.get(colour)?;
+        let mut visited: HashSet<usize> = HashSet::new();
+        visited.insert(start);
+        let mut to_search = vec![start];
+        while let Some(current) = to_search.pop() {
+            for (_, inner) in self.rules[current].contents.iter() {
+                if visited.insert(self.index[inner]) {
+                    to_search.push(self.index[inner]);
+                }
+            }
+        }
+        Some(visited)
+    }
+
+    // Number of distinct bag colours that can eventually hold a `colour` bag.
+    pub fn containers_of(&self, colour: &str) -> Option<usize> {
+        self.reachable_to(colour)
+            .map(|containers| containers.len() - 1)
+    }
+
+    // Total number of bags required inside a single `colour` bag.
+    pub fn contained_in(&self, colour: &str) -> Option<u64> {
+        let start = *self.index.get(colour)?;
+        let mut memo: HashMap<usize, u64> = HashMap::new();
+        Some(self.count_contents(start, &mut memo))
+    }
+
+    fn count_contents(&self, node: usize, memo: &mut HashMap<usize, u64>) -> u64 {
+        if let Some(&count) = memo.get(&node) {
+            return count;
+        }
+        let mut count = 0;
+        for (number, colour) in self.rules[node].contents.iter() {
+            count += number + number * self.count_contents(self.index[colour], memo);
+        }
+        memo.insert(node, count);
+        count
+    }
+}
+
+pub fn part1(graph: &BagGraph) -> Option<usize> {
+    graph.containers_of("shiny gold")
+}
+
+pub fn part2(graph: &BagGraph) -> Option<u64> {
+    graph.contained_in("shiny gold")
+}
+
+mod test {
+    const EXAMPLE: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    fn graph_from(input: &str) -> Result<super::BagGraph, super::BagGraphError> {
+        super::BagGraph::from_lines(input.lines().map(String::from))
+    }
+
+    #[test]
+    fn example_part1() {
+        let graph = graph_from(EXAMPLE).unwrap();
+        assert_eq!(super::part1(&graph), Some(4));
+    }
+
+    #[test]
+    fn example_part2() {
+        let graph = graph_from(EXAMPLE).unwrap();
+        assert_eq!(super::part2(&graph), Some(32));
+        let graph = graph_from(include_str!("../test_input.txt")).unwrap();
+        assert_eq!(super::part2(&graph), Some(126));
+        assert_eq!(graph.contained_in("plaid magenta"), None);
+    }
+
+    #[test]
+    fn dangling_reference() {
+        use super::BagGraphError;
+        let input = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 1 plaid magenta bag.";
+        match graph_from(input) {
+            Err(BagGraphError::DanglingReference { rule, missing }) => {
+                assert_eq!(rule.line, 2);
+                assert_eq!(missing, "plaid magenta");
+            }
+            _ => panic!("expected a dangling reference"),
+        }
+    }
+
+    #[test]
+    fn cycle() {
+        use super::BagGraphError;
+        let input = "shiny gold bags contain 2 dark red bags.
+dark red bags contain 1 dark blue bag.
+
+dark blue bags contain 3 shiny gold bags, 1 faded blue bag.
+faded blue bags contain no other bags.";
+        match graph_from(input) {
+            Err(BagGraphError::Cycle(rules)) => {
+                let lines: Vec<usize> = rules.iter().map(|rule| rule.line).collect();
+                assert_eq!(lines, vec![1, 2, 4]);
+            }
+            _ => panic!("expected a cycle"),
+        }
+    }
+
+    #[test]
+    fn malformed_and_duplicate() {
+        use super::BagGraphError;
+        let input = "shiny gold bags contain two dark red bags.";
+        assert_eq!(
+            graph_from(input).err(),
+            Some(BagGraphError::Malformed {
+                line: 1,
+                text: String::from(input)
+            })
+        );
+        let input = "shiny gold bags contain no other bags.
+shiny gold bags contain no other bags.";
+        match graph_from(input) {
+            Err(BagGraphError::DuplicateRule { first, duplicate }) => {
+                assert_eq!((first.line, duplicate.line), (1, 2));
+            }
+            _ => panic!("expected a duplicate rule"),
+        }
+    }
+}
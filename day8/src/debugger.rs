@@ -0,0 +1,238 @@
+use crate::machine::{Instruction, Machine, MNEMONICS, REGISTERS};
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "commands:
+  s, step [n]             execute n instructions (default 1)
+  c, continue             run until a breakpoint is hit or the machine halts
+  b, break [pc | op]      break at a pc or before any instruction of that kind, lists breakpoints without an argument
+  d, delete <n>           remove breakpoint n
+  w, watch                toggle printing the accumulator whenever it changes
+  t, trace [n]            print the last n executed instructions (default all)
+  p, print                print the machine state
+  r, registers            print every register and the output so far
+  l, list [n]             print n instructions around the pc (default 5)
+  q, quit                 exit the debugger";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Op(String),
+}
+
+impl Breakpoint {
+    fn from_str(s: &str) -> Option<Self> {
+        if MNEMONICS.contains(&s) {
+            Some(Breakpoint::Op(String::from(s)))
+        } else {
+            s.parse().ok().map(Breakpoint::Pc)
+        }
+    }
+
+    fn matches(&self, pc: usize, instruction: &Instruction) -> bool {
+        match self {
+            Breakpoint::Pc(break_pc) => *break_pc == pc,
+            Breakpoint::Op(op) => instruction.mnemonic() == op,
+        }
+    }
+}
+
+pub struct Debugger {
+    machine: Machine,
+    breakpoints: Vec<Breakpoint>,
+    watch_acc: bool,
+}
+
+impl Debugger {
+    pub fn new(mut machine: Machine) -> Self {
+        machine.record_trace();
+        Debugger {
+            machine,
+            breakpoints: Vec::new(),
+            watch_acc: false,
+        }
+    }
+
+    pub fn run(&mut self, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        writeln!(output, "{}", HELP)?;
+        self.print_state(output)?;
+        write!(output, "(day8) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            if !self.execute(line?.trim(), output)? {
+                return Ok(());
+            }
+            write!(output, "(day8) ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+
+    // Returns false once the user asks to quit.
+    fn execute(&mut self, command: &str, output: &mut impl Write) -> io::Result<bool> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let arg = words.next();
+        match name {
+            "" => (),
+            "s" | "step" => match arg.map(|n| n.parse::<usize>()).unwrap_or(Ok(1)) {
+                Ok(count) => {
+                    for _ in 0..count {
+                        if !self.step(output)? {
+                            break;
+                        }
+                    }
+                    self.print_state(output)?;
+                }
+                Err(_) => writeln!(output, "step count must be a positive integer")?,
+            },
+            "c" | "continue" => {
+                while self.step(output)? {
+                    let pc = self.machine.pc();
+                    if let Some(instruction) = self.machine.current() {
+                        if let Some(index) = self
+                            .breakpoints
+                            .iter()
+                            .position(|b| b.matches(pc, &instruction))
+                        {
+                            writeln!(output, "hit breakpoint {}", index)?;
+                            break;
+                        }
+                    }
+                }
+                self.print_state(output)?;
+            }
+            "b" | "break" => match arg {
+                None => {
+                    for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                        match breakpoint {
+                            Breakpoint::Pc(pc) => writeln!(output, "{}: pc {}", index, pc)?,
+                            Breakpoint::Op(op) => writeln!(output, "{}: {}", index, op)?,
+                        }
+                    }
+                }
+                Some(target) => match Breakpoint::from_str(target) {
+                    Some(breakpoint) => {
+                        writeln!(output, "breakpoint {} set", self.breakpoints.len())?;
+                        self.breakpoints.push(breakpoint);
+                    }
+                    None => writeln!(output, "breakpoints must be a pc or an instruction kind")?,
+                },
+            },
+            "d" | "delete" => match arg.and_then(|n| n.parse::<usize>().ok()) {
+                Some(index) if index < self.breakpoints.len() => {
+                    self.breakpoints.remove(index);
+                }
+                _ => writeln!(output, "no such breakpoint")?,
+            },
+            "w" | "watch" => {
+                self.watch_acc = !self.watch_acc;
+                let state = if self.watch_acc { "on" } else { "off" };
+                writeln!(output, "watching accumulator {}", state)?;
+            }
+            "t" | "trace" => {
+                let trace = self.machine.trace();
+                let count = arg
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .unwrap_or(trace.len());
+                for entry in trace.iter().skip(trace.len().saturating_sub(count)) {
+                    let instruction = entry.instruction.to_string();
+                    writeln!(output, "{:>5}  {:<10} acc {}", entry.pc, instruction, entry.acc)?;
+                }
+            }
+            "p" | "print" => self.print_state(output)?,
+            "r" | "registers" => {
+                for register in REGISTERS.iter() {
+                    writeln!(output, "{:>3} {}", register.name(), self.machine.register(*register))?;
+                }
+                writeln!(output, "output {:?}", self.machine.output())?;
+            }
+            "l" | "list" => {
+                let radius = arg.and_then(|n| n.parse::<usize>().ok()).unwrap_or(5);
+                let pc = self.machine.pc();
+                let program = self.machine.program();
+                let start = pc.saturating_sub(radius).min(program.len());
+                let end = (pc + radius + 1).min(program.len());
+                for (index, instruction) in program[start..end].iter().enumerate() {
+                    let marker = if start + index == pc { "=>" } else { "  " };
+                    writeln!(output, "{} {:>5}  {}", marker, start + index, instruction)?;
+                }
+            }
+            "q" | "quit" => return Ok(false),
+            "h" | "help" => writeln!(output, "{}", HELP)?,
+            _ => writeln!(output, "unknown command \"{}\", try help", name)?,
+        }
+        Ok(true)
+    }
+
+    // Returns false once the machine has halted.
+    fn step(&mut self, output: &mut impl Write) -> io::Result<bool> {
+        let pc = self.machine.pc();
+        let instruction = match (self.machine.current(), self.machine.halted()) {
+            (Some(instruction), None) => instruction,
+            _ => {
+                writeln!(output, "machine has halted")?;
+                return Ok(false);
+            }
+        };
+        let acc_before = self.machine.acc();
+        let output_before = self.machine.output().len();
+        let halted = self.machine.step();
+        for value in self.machine.output()[output_before..].iter() {
+            writeln!(output, "out: {}", value)?;
+        }
+        if self.watch_acc && self.machine.acc() != acc_before {
+            writeln!(
+                output,
+                "acc changed {} -> {} at pc {} ({})",
+                acc_before,
+                self.machine.acc(),
+                pc,
+                instruction
+            )?;
+        }
+        if let Some(reason) = halted {
+            writeln!(output, "machine halted: {}", reason)?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn print_state(&self, output: &mut impl Write) -> io::Result<()> {
+        let next = match self.machine.current() {
+            Some(instruction) => instruction.to_string(),
+            None => String::from("<end of program>"),
+        };
+        write!(output, "pc {} acc {} next {}", self.machine.pc(), self.machine.acc(), next)?;
+        match self.machine.halted() {
+            Some(reason) => writeln!(output, " ({})", reason),
+            None => writeln!(output),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn breakpoints_and_watch() {
+        use super::Debugger;
+        use crate::machine::{assemble, Machine};
+        let source = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+        let program = assemble(source.lines().map(String::from)).unwrap();
+        let mut debugger = Debugger::new(Machine::new(program));
+        let commands = "break 4\nwatch\ncontinue\ntrace 2\ncontinue\nquit\n";
+        let mut output: Vec<u8> = Vec::new();
+        debugger.run(commands.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("acc changed 0 -> 1 at pc 1 (acc +1)"));
+        assert!(output.contains("hit breakpoint 0\npc 4 acc 5 next jmp -3"));
+        assert!(output.contains("    7  jmp -4     acc 2\n    3  acc +3     acc 5\n"));
+        assert!(output.contains("machine halted: infinite loop at pc 1\npc 1 acc 5 next acc +1 (infinite loop at pc 1)"));
+    }
+}
@@ -0,0 +1,590 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Register {
+    Acc,
+    A,
+    B,
+    C,
+    D,
+}
+
+pub const REGISTERS: [Register; 5] = [Register::Acc, Register::A, Register::B, Register::C, Register::D];
+
+impl Register {
+    fn from_str(s: &str) -> Option<Self> {
+        REGISTERS.iter().copied().find(|register| register.name() == s)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Register::Acc => "acc",
+            Register::A => "a",
+            Register::B => "b",
+            Register::C => "c",
+            Register::D => "d",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Immediate(i32),
+    Register(Register),
+}
+
+impl Operand {
+    fn from_str(s: &str) -> Result<Self, &'static str> {
+        match Register::from_str(s) {
+            Some(register) => Ok(Operand::Register(register)),
+            None => parse_immediate(s).map(Operand::Immediate),
+        }
+    }
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Immediate(value) => write!(f, "{:+}", value),
+            Operand::Register(register) => write!(f, "{}", register.name()),
+        }
+    }
+}
+
+fn parse_immediate(s: &str) -> Result<i32, &'static str> {
+    s.parse().map_err(|_| "argument is not a signed integer")
+}
+
+fn parse_register(s: &str) -> Result<Register, &'static str> {
+    Register::from_str(s).ok_or("unknown register")
+}
+
+// The handheld's original acc/jmp/nop plus the teaching extensions, which
+// work on the registers in `REGISTERS`. Jump offsets are relative to the
+// jump itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Acc(i32),
+    Jmp(i32),
+    Nop(i32),
+    // Load an immediate into a register.
+    Ld(Register, i32),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    // Jump if the register is zero.
+    Jz(Register, i32),
+    // Jump if the register is not zero.
+    Jnz(Register, i32),
+    // Append the register's value to the machine's output.
+    Out(Register),
+}
+
+pub const MNEMONICS: [&str; 9] = ["acc", "jmp", "nop", "ld", "add", "mul", "jz", "jnz", "out"];
+
+impl Instruction {
+    pub fn from_str(s: &str) -> Result<Self, &'static str> {
+        let mut words = s.split(' ');
+        let op = words.next().unwrap_or("");
+        if !MNEMONICS.contains(&op) {
+            return Err("unknown operation");
+        }
+        let args: Vec<&str> = words.collect();
+        let expected = match op {
+            "acc" | "jmp" | "nop" | "out" => 1,
+            _ => 2,
+        };
+        if args.len() < expected {
+            return Err("missing argument");
+        }
+        if args.len() > expected {
+            return Err("too many arguments");
+        }
+        match op {
+            "acc" => Ok(Instruction::Acc(parse_immediate(args[0])?)),
+            "jmp" => Ok(Instruction::Jmp(parse_immediate(args[0])?)),
+            "nop" => Ok(Instruction::Nop(parse_immediate(args[0])?)),
+            "ld" => Ok(Instruction::Ld(parse_register(args[0])?, parse_immediate(args[1])?)),
+            "add" => Ok(Instruction::Add(parse_register(args[0])?, Operand::from_str(args[1])?)),
+            "mul" => Ok(Instruction::Mul(parse_register(args[0])?, Operand::from_str(args[1])?)),
+            "jz" => Ok(Instruction::Jz(parse_register(args[0])?, parse_immediate(args[1])?)),
+            "jnz" => Ok(Instruction::Jnz(parse_register(args[0])?, parse_immediate(args[1])?)),
+            "out" => Ok(Instruction::Out(parse_register(args[0])?)),
+            _ => unreachable!(),
+        }
+    }
+
+    // The corrupted instruction swap from part 2, acc is never corrupted.
+    pub fn flipped(&self) -> Option<Self> {
+        match *self {
+            Instruction::Jmp(arg) => Some(Instruction::Nop(arg)),
+            Instruction::Nop(arg) => Some(Instruction::Jmp(arg)),
+            _ => None,
+        }
+    }
+
+    // Whether control flow after this instruction depends on register values.
+    pub fn is_conditional(&self) -> bool {
+        matches!(self, Instruction::Jz(_, _) | Instruction::Jnz(_, _))
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Acc(_) => "acc",
+            Instruction::Jmp(_) => "jmp",
+            Instruction::Nop(_) => "nop",
+            Instruction::Ld(_, _) => "ld",
+            Instruction::Add(_, _) => "add",
+            Instruction::Mul(_, _) => "mul",
+            Instruction::Jz(_, _) => "jz",
+            Instruction::Jnz(_, _) => "jnz",
+            Instruction::Out(_) => "out",
+        }
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.mnemonic())?;
+        match self {
+            Instruction::Acc(arg) | Instruction::Jmp(arg) | Instruction::Nop(arg) => {
+                write!(f, "{:+}", arg)
+            }
+            Instruction::Ld(register, arg)
+            | Instruction::Jz(register, arg)
+            | Instruction::Jnz(register, arg) => write!(f, "{} {:+}", register.name(), arg),
+            Instruction::Add(register, operand) | Instruction::Mul(register, operand) => {
+                write!(f, "{} {}", register.name(), operand)
+            }
+            Instruction::Out(register) => write!(f, "{}", register.name()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} \"{}\"", self.line, self.reason, self.text)
+    }
+}
+
+impl Error for ParseError {}
+
+pub fn assemble(lines: impl Iterator<Item = String>) -> Result<Vec<Instruction>, ParseError> {
+    lines
+        .enumerate()
+        .map(|(index, text)| {
+            Instruction::from_str(&text).map_err(|reason| ParseError {
+                line: index + 1,
+                text,
+                reason,
+            })
+        })
+        .collect()
+}
+
+pub fn disassemble(program: &[Instruction]) -> Vec<String> {
+    program.iter().map(|instruction| instruction.to_string()).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HaltReason {
+    // The program counter moved to just past the last instruction.
+    Terminated,
+    // The machine returned to an earlier state with this pc, so it would repeat
+    // forever. Without conditional jumps that's the first time an instruction
+    // comes round again, with them the pc may repeat many times first.
+    InfiniteLoop(usize),
+    // A jump targeted this pc, which is outside the program.
+    OutOfBounds(i64),
+    // The machine ran this many steps without halting, see `limit_steps`.
+    StepLimit(u64),
+}
+
+impl Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HaltReason::Terminated => write!(f, "terminated normally"),
+            HaltReason::InfiniteLoop(pc) => write!(f, "infinite loop at pc {}", pc),
+            HaltReason::OutOfBounds(target) => write!(f, "jumped out of bounds to {}", target),
+            HaltReason::StepLimit(steps) => write!(f, "gave up after {} steps", steps),
+        }
+    }
+}
+
+// The accumulator is the value after the instruction has executed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub acc: i32,
+}
+
+// Programs made of acc/jmp/nop always take the same path, so revisiting a pc
+// means an infinite loop. Once conditional jumps are involved the machine
+// only loops forever if it revisits a pc with identical registers. Keeping
+// every state would grow with the run, so Brent's algorithm compares each
+// state with a single saved one instead, saving a new one whenever the
+// distance since the last save reaches the next power of two. That catches
+// any cycle within about two laps of it, using constant memory, though not
+// necessarily at the first repeated state.
+enum LoopDetector {
+    Visited(Vec<bool>),
+    Brent {
+        saved: Option<(usize, [i32; 5])>,
+        power: u64,
+        distance: u64,
+    },
+}
+
+pub struct Machine {
+    program: Vec<Instruction>,
+    loop_detector: LoopDetector,
+    pc: usize,
+    registers: [i32; 5],
+    output: Vec<i32>,
+    halted: Option<HaltReason>,
+    trace: Option<Vec<TraceEntry>>,
+    steps: u64,
+    step_limit: Option<u64>,
+}
+
+impl Machine {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        let loop_detector = if program.iter().any(|instruction| instruction.is_conditional()) {
+            LoopDetector::Brent {
+                saved: None,
+                power: 1,
+                distance: 0,
+            }
+        } else {
+            LoopDetector::Visited(vec![false; program.len()])
+        };
+        let mut machine = Machine {
+            program,
+            loop_detector,
+            pc: 0,
+            registers: [0; 5],
+            output: Vec::new(),
+            halted: None,
+            trace: None,
+            steps: 0,
+            step_limit: None,
+        };
+        machine.check_halted();
+        machine
+    }
+
+    // Keeps every executed instruction from now on, see `trace`.
+    pub fn record_trace(&mut self) {
+        if self.trace.is_none() {
+            self.trace = Some(Vec::new());
+        }
+    }
+
+    // Halts with `HaltReason::StepLimit` once `max_steps` instructions have
+    // run, for programs that take too long to finish or to repeat a state. A
+    // limit the machine has already reached halts it on the next step.
+    pub fn limit_steps(&mut self, max_steps: u64) {
+        self.step_limit = Some(max_steps);
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    fn limit_reached(&self) -> bool {
+        self.step_limit.is_some_and(|limit| self.steps >= limit)
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn acc(&self) -> i32 {
+        self.register(Register::Acc)
+    }
+
+    pub fn register(&self, register: Register) -> i32 {
+        self.registers[register.index()]
+    }
+
+    // Every value written by `out` so far.
+    pub fn output(&self) -> &[i32] {
+        &self.output
+    }
+
+    pub fn halted(&self) -> Option<HaltReason> {
+        self.halted
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    // The instruction that the next call to `step` will execute.
+    pub fn current(&self) -> Option<Instruction> {
+        self.program.get(self.pc).copied()
+    }
+
+    fn value(&self, operand: Operand) -> i32 {
+        match operand {
+            Operand::Immediate(value) => value,
+            Operand::Register(register) => self.register(register),
+        }
+    }
+
+    // Executes a single instruction, returns the halt reason once the machine
+    // can't continue. An out of bounds jump leaves the pc on the jump.
+    // Arithmetic wraps on overflow.
+    pub fn step(&mut self) -> Option<HaltReason> {
+        if self.halted.is_some() {
+            return self.halted;
+        }
+        if self.limit_reached() {
+            self.halted = Some(HaltReason::StepLimit(self.steps));
+            return self.halted;
+        }
+        let instruction = self.program[self.pc];
+        if let LoopDetector::Visited(visited) = &mut self.loop_detector {
+            visited[self.pc] = true;
+        }
+        self.steps += 1;
+        let pc = self.pc as i64;
+        let mut target = pc + 1;
+        match instruction {
+            Instruction::Acc(arg) => {
+                let acc = &mut self.registers[Register::Acc.index()];
+                *acc = acc.wrapping_add(arg);
+            }
+            Instruction::Nop(_) => (),
+            Instruction::Jmp(arg) => target = pc + arg as i64,
+            Instruction::Ld(register, value) => self.registers[register.index()] = value,
+            Instruction::Add(register, operand) => {
+                let value = self.value(operand);
+                let destination = &mut self.registers[register.index()];
+                *destination = destination.wrapping_add(value);
+            }
+            Instruction::Mul(register, operand) => {
+                let value = self.value(operand);
+                let destination = &mut self.registers[register.index()];
+                *destination = destination.wrapping_mul(value);
+            }
+            Instruction::Jz(register, arg) => {
+                if self.register(register) == 0 {
+                    target = pc + arg as i64;
+                }
+            }
+            Instruction::Jnz(register, arg) => {
+                if self.register(register) != 0 {
+                    target = pc + arg as i64;
+                }
+            }
+            Instruction::Out(register) => self.output.push(self.register(register)),
+        }
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry {
+                pc: self.pc,
+                instruction,
+                acc: self.registers[Register::Acc.index()],
+            });
+        }
+        if target < 0 || target > self.program.len() as i64 {
+            self.halted = Some(HaltReason::OutOfBounds(target));
+        } else {
+            self.pc = target as usize;
+            self.check_halted();
+        }
+        if self.halted.is_none() && self.limit_reached() {
+            self.halted = Some(HaltReason::StepLimit(self.steps));
+        }
+        self.halted
+    }
+
+    pub fn run(&mut self) -> HaltReason {
+        loop {
+            if let Some(reason) = self.step() {
+                return reason;
+            }
+        }
+    }
+
+    fn check_halted(&mut self) {
+        if self.pc == self.program.len() {
+            self.halted = Some(HaltReason::Terminated);
+            return;
+        }
+        let state = (self.pc, self.registers);
+        let looped = match &mut self.loop_detector {
+            LoopDetector::Visited(visited) => visited[self.pc],
+            LoopDetector::Brent {
+                saved,
+                power,
+                distance,
+            } => {
+                if *saved == Some(state) {
+                    true
+                } else {
+                    if saved.is_none() || distance == power {
+                        if saved.is_some() {
+                            *power *= 2;
+                        }
+                        *saved = Some(state);
+                        *distance = 0;
+                    }
+                    *distance += 1;
+                    false
+                }
+            }
+        };
+        if looped {
+            self.halted = Some(HaltReason::InfiniteLoop(self.pc));
+        }
+    }
+}
+
+mod test {
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn example_part1() {
+        use super::{assemble, HaltReason, Machine};
+        let program = assemble(EXAMPLE.lines().map(String::from)).unwrap();
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run(), HaltReason::InfiniteLoop(1));
+        assert_eq!(machine.acc(), 5);
+    }
+
+    #[test]
+    fn halt_reasons() {
+        use super::{HaltReason, Instruction, Machine};
+        let mut machine = Machine::new(vec![Instruction::Acc(2), Instruction::Jmp(-2)]);
+        assert_eq!(machine.run(), HaltReason::OutOfBounds(-1));
+        assert_eq!((machine.pc(), machine.acc()), (1, 2));
+        let mut machine = Machine::new(vec![Instruction::Jmp(3), Instruction::Nop(0)]);
+        assert_eq!(machine.run(), HaltReason::OutOfBounds(3));
+        let mut machine = Machine::new(vec![Instruction::Jmp(2), Instruction::Nop(0)]);
+        assert_eq!(machine.run(), HaltReason::Terminated);
+        assert_eq!(Machine::new(Vec::new()).halted(), Some(HaltReason::Terminated));
+    }
+
+    #[test]
+    fn disassemble_round_trip() {
+        use super::{assemble, disassemble};
+        let program = assemble(EXAMPLE.lines().map(String::from)).unwrap();
+        let source = disassemble(&program);
+        assert_eq!(source, EXAMPLE.lines().collect::<Vec<&str>>());
+        assert_eq!(assemble(source.into_iter()).unwrap(), program);
+    }
+
+    #[test]
+    fn bad_lines() {
+        use super::{assemble, ParseError};
+        let program = vec![String::from("nop +0"), String::from("div +2")];
+        assert_eq!(
+            assemble(program.into_iter()),
+            Err(ParseError {
+                line: 2,
+                text: String::from("div +2"),
+                reason: "unknown operation"
+            })
+        );
+        let program = vec![String::from("acc")];
+        assert_eq!(assemble(program.into_iter()).unwrap_err().reason, "missing argument");
+        let program = vec![String::from("jmp +x")];
+        assert_eq!(
+            assemble(program.into_iter()).unwrap_err().reason,
+            "argument is not a signed integer"
+        );
+        let program = vec![String::from("ld e +1")];
+        assert_eq!(assemble(program.into_iter()).unwrap_err().reason, "unknown register");
+        let program = vec![String::from("nop +0 +1")];
+        assert_eq!(assemble(program.into_iter()).unwrap_err().reason, "too many arguments");
+    }
+
+    #[test]
+    fn extended_instructions() {
+        use super::{assemble, disassemble, HaltReason, Machine, Register};
+        // Prints 5 factorial, counting b down from 5.
+        let source = "ld a +1
+ld b +5
+mul a b
+add b -1
+jnz b -2
+out a
+acc +3
+add acc a";
+        let program = assemble(source.lines().map(String::from)).unwrap();
+        assert_eq!(disassemble(&program), source.lines().collect::<Vec<&str>>());
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run(), HaltReason::Terminated);
+        assert_eq!(machine.output(), &[120]);
+        assert_eq!(machine.register(Register::B), 0);
+        assert_eq!(machine.acc(), 123);
+
+        let program = assemble("ld a +2\njnz a +0".lines().map(String::from)).unwrap();
+        assert_eq!(Machine::new(program).run(), HaltReason::InfiniteLoop(1));
+    }
+
+    #[test]
+    fn long_conditional_runs() {
+        use super::{assemble, HaltReason, Machine};
+        // Counting down never repeats a state, so it has to run to the end.
+        let program = assemble("ld a +300000\nadd a -1\njnz a -1".lines().map(String::from)).unwrap();
+        let mut machine = Machine::new(program.clone());
+        assert_eq!(machine.run(), HaltReason::Terminated);
+        assert_eq!(machine.steps(), 600001);
+        let mut machine = Machine::new(program.clone());
+        machine.limit_steps(1000);
+        assert_eq!(machine.run(), HaltReason::StepLimit(1000));
+        let mut machine = Machine::new(program.clone());
+        machine.limit_steps(0);
+        assert_eq!(machine.run(), HaltReason::StepLimit(0));
+        assert_eq!(machine.steps(), 0);
+        // Lowering the limit below the steps already taken stops at once.
+        let mut machine = Machine::new(program);
+        for _ in 0..10 {
+            machine.step();
+        }
+        machine.limit_steps(5);
+        assert_eq!(machine.run(), HaltReason::StepLimit(10));
+
+        // Cycles a through 3, 2, 1, 0 forever after a long lead in.
+        let source = "ld a +100000
+add a -1
+jnz a -1
+ld a +3
+add a -1
+jnz a -1
+jmp -3";
+        let program = assemble(source.lines().map(String::from)).unwrap();
+        match Machine::new(program).run() {
+            HaltReason::InfiniteLoop(pc) => assert!((3..=6).contains(&pc)),
+            reason => panic!("expected an infinite loop, got {}", reason),
+        }
+    }
+}
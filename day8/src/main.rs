@@ -1,60 +1,73 @@
 #![allow(dead_code)]
-use std::{env, fs::File, io::{self, BufRead}, path::Path};
+use std::{env, error::Error, fs::File, io::{self, BufRead}, path::Path};
 
-fn main() -> Result<(), io::Error> {
+mod debugger;
+mod machine;
+mod repair;
+mod trace;
+
+use machine::Machine;
+
+fn main() -> Result<(), Box<dyn Error>> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
-    let program: Vec<String> = io::BufReader::new(input_file).lines().map(|l| l.unwrap()).collect();
+    let program = match machine::assemble(io::BufReader::new(input_file).lines().map(|l| l.unwrap())) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        let (acc, _) = part1(&program);
-        println!("Final accumulator value {}", acc);
+    if env::args().nth(1) == Some(String::from("part1")) {
+        let mut machine = Machine::new(program);
+        let reason = machine.run();
+        println!("Program halted: {}", reason);
+        println!("Final accumulator value {}", machine.acc());
         std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        println!("Final accumulator value {}", part2(&program));
+    } else if env::args().nth(1) == Some(String::from("part2")) {
+        let fix = repair::repair(&program).ok_or("No valid program found")?;
+        println!("Flipped instruction {} from {} to {}", fix.index, fix.original, fix.replacement);
+        println!("Final accumulator value {}", fix.acc);
         std::process::exit(0);
-    } else {
-        println!("usage: cargo run (part1 | part2)");
-        std::process::exit(1);
-    }
-}
-
-fn part1(program: &Vec<String>) -> (i32, i32) {
-    let mut accumulator = 0;
-    let mut visited = vec![false; program.len()];
-    let mut program_counter = 0;
-    while program_counter < program.len() && !visited[program_counter] {
-        let (op, arg) = program[program_counter].split_at(3);
-        visited[program_counter] = true;
-        let signed_arg: i32 = arg[1..].parse().unwrap();
-        match op {
-            "acc" => {
-                accumulator += signed_arg;
-                program_counter += 1;
-            },
-            "nop" => program_counter += 1,
-            "jmp" => program_counter = (program_counter as i32 + signed_arg) as usize,
-            _ => unreachable!(),
+    } else if env::args().nth(1) == Some(String::from("debug")) {
+        let mut debugger = debugger::Debugger::new(Machine::new(program));
+        debugger.run(io::stdin().lock(), &mut io::stdout())?;
+        std::process::exit(0);
+    } else if env::args().nth(1) == Some(String::from("trace")) {
+        let format = env::args().nth(2).and_then(|f| trace::Format::from_str(&f));
+        if let Some(format) = format {
+            let mut machine = Machine::new(program);
+            machine.record_trace();
+            eprintln!("Program halted: {}", machine.run());
+            print!("{}", trace::export(machine.trace(), format));
+            std::process::exit(0);
         }
-    }
-    (accumulator, program_counter as i32)
-}
-
-fn part2(program: &Vec<String>) -> i32 {
-    let flip_map: Vec<bool> = program.iter().map(|instruction| &instruction[..3] != "acc").collect();
-    for (index, flip) in flip_map.iter().enumerate() {
-        if *flip {
-            let mut new_program = program.clone();
-            new_program[index] = if &program[index][..3] == "nop" {
-                format!("jmp{}", &program[index][3..])
-            } else {
-                format!("nop{}", &program[index][3..])
-            };
-            let (acc, pc) = part1(&new_program);
-            if pc == program.len() as i32 {
-                return acc;
+        println!("usage: cargo run trace (json | csv)");
+        std::process::exit(1);
+    } else if env::args().nth(1) == Some(String::from("exec")) {
+        let path = env::args().nth(2).ok_or("usage: cargo run exec <program> [--max-steps <steps>]")?;
+        let source = io::BufReader::new(File::open(path)?);
+        let program = match machine::assemble(source.lines().map(|l| l.unwrap())) {
+            Ok(program) => program,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
+        };
+        let mut machine = Machine::new(program);
+        if env::args().nth(3).as_deref() == Some("--max-steps") {
+            let max_steps = env::args().nth(4).ok_or("--max-steps needs a value")?;
+            machine.limit_steps(max_steps.parse()?);
         }
+        let reason = machine.run();
+        for value in machine.output() {
+            println!("{}", value);
+        }
+        eprintln!("Program halted: {}", reason);
+        std::process::exit(0);
+    } else {
+        println!("usage: cargo run (part1 | part2 | debug | trace (json | csv) | exec <program> [--max-steps <steps>])");
+        std::process::exit(1);
     }
-    unreachable!("No valid program found");
 }
@@ -0,0 +1,129 @@
+use crate::machine::{HaltReason, Instruction, Machine};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repair {
+    pub index: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+    pub acc: i32,
+}
+
+// Where control goes after executing `instruction` at `index`, None for jumps
+// before the start of the program.
+fn successor(index: usize, instruction: Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::Jmp(arg) => {
+            let target = index as i64 + arg as i64;
+            if target < 0 {
+                None
+            } else {
+                Some(target as usize)
+            }
+        }
+        _ => Some(index + 1),
+    }
+}
+
+// Marks every instruction that terminates the unmodified program when
+// execution starts there. Index `program.len()` is the end of the program.
+fn reaches_end(program: &[Instruction]) -> Vec<bool> {
+    let end = program.len();
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); end + 1];
+    for (index, &instruction) in program.iter().enumerate() {
+        if let Some(next) = successor(index, instruction) {
+            if next <= end {
+                predecessors[next].push(index);
+            }
+        }
+    }
+    let mut reaches = vec![false; end + 1];
+    reaches[end] = true;
+    let mut to_search: VecDeque<usize> = VecDeque::new();
+    to_search.push_back(end);
+    while let Some(current) = to_search.pop_front() {
+        for &previous in predecessors[current].iter() {
+            if !reaches[previous] {
+                reaches[previous] = true;
+                to_search.push_back(previous);
+            }
+        }
+    }
+    reaches
+}
+
+// Finds the single jmp/nop flip that lets the program terminate. Every
+// instruction on the original looping path can't reach the end, so a flip
+// whose new successor can reach the end never passes back through itself.
+// Returns None if the program already terminates or can't be repaired.
+// Conditional jumps make control flow depend on register values, so programs
+// using them are never repaired.
+pub fn repair(program: &[Instruction]) -> Option<Repair> {
+    if program.iter().any(|instruction| instruction.is_conditional()) {
+        return None;
+    }
+    let reaches = reaches_end(program);
+    if reaches[0] {
+        return None;
+    }
+    let mut visited = vec![false; program.len()];
+    let mut pc = 0;
+    while pc < program.len() && !visited[pc] {
+        visited[pc] = true;
+        let original = program[pc];
+        if let Some(replacement) = original.flipped() {
+            if let Some(next) = successor(pc, replacement) {
+                if next <= program.len() && reaches[next] {
+                    let mut patched = program.to_vec();
+                    patched[pc] = replacement;
+                    let mut machine = Machine::new(patched);
+                    if machine.run() == HaltReason::Terminated {
+                        return Some(Repair {
+                            index: pc,
+                            original,
+                            replacement,
+                            acc: machine.acc(),
+                        });
+                    }
+                }
+            }
+        }
+        pc = successor(pc, original)?;
+    }
+    None
+}
+
+mod test {
+    #[test]
+    fn example_part2() {
+        use super::{repair, Repair};
+        use crate::machine::{assemble, Instruction};
+        let source = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+        let program = assemble(source.lines().map(String::from)).unwrap();
+        assert_eq!(
+            repair(&program),
+            Some(Repair {
+                index: 7,
+                original: Instruction::Jmp(-4),
+                replacement: Instruction::Nop(-4),
+                acc: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn already_terminates() {
+        use super::repair;
+        use crate::machine::Instruction;
+        let program = vec![Instruction::Nop(1), Instruction::Acc(1)];
+        assert_eq!(repair(&program), None);
+    }
+}
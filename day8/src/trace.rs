@@ -0,0 +1,75 @@
+use crate::machine::TraceEntry;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+// Steps are numbered from 0 in execution order, acc is the value after the
+// instruction has executed.
+pub fn export(trace: &[TraceEntry], format: Format) -> String {
+    match format {
+        Format::Json => to_json(trace),
+        Format::Csv => to_csv(trace),
+    }
+}
+
+fn to_csv(trace: &[TraceEntry]) -> String {
+    let mut out = String::from("step,pc,instruction,acc\n");
+    for (step, entry) in trace.iter().enumerate() {
+        writeln!(out, "{},{},{},{}", step, entry.pc, entry.instruction, entry.acc).unwrap();
+    }
+    out
+}
+
+fn to_json(trace: &[TraceEntry]) -> String {
+    let mut out = String::from("[");
+    for (step, entry) in trace.iter().enumerate() {
+        if step > 0 {
+            out.push(',');
+        }
+        write!(
+            out,
+            "\n  {{\"step\": {}, \"pc\": {}, \"instruction\": \"{}\", \"acc\": {}}}",
+            step, entry.pc, entry.instruction, entry.acc
+        )
+        .unwrap();
+    }
+    out.push_str("\n]\n");
+    out
+}
+
+mod test {
+    #[test]
+    fn csv_and_json() {
+        use super::{export, Format};
+        use crate::machine::{HaltReason, Instruction, Machine};
+        let mut machine = Machine::new(vec![Instruction::Acc(3), Instruction::Jmp(-1)]);
+        machine.record_trace();
+        assert_eq!(machine.run(), HaltReason::InfiniteLoop(0));
+        assert_eq!(
+            export(machine.trace(), Format::Csv),
+            "step,pc,instruction,acc\n0,0,acc +3,3\n1,1,jmp -1,3\n"
+        );
+        assert_eq!(
+            export(machine.trace(), Format::Json),
+            "[
+  {\"step\": 0, \"pc\": 0, \"instruction\": \"acc +3\", \"acc\": 3},
+  {\"step\": 1, \"pc\": 1, \"instruction\": \"jmp -1\", \"acc\": 3}
+]
+"
+        );
+    }
+}
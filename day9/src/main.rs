@@ -1,66 +1,68 @@
 #![allow(dead_code)]
-use std::{cmp::Ordering, collections::HashMap, env, fs::File, io::{self, BufRead}, path::Path};
+use std::{env, error::Error, fs::File, io::{self, BufRead}, ops::Range, path::Path};
+
+mod xmas;
+
+const DEFAULT_PREAMBLE_LENGTH: usize = 25;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let preamble_length = match env::args().nth(2) {
+        Some(arg) => arg.parse::<usize>().ok().filter(|&n| n > 0).ok_or("preamble length must be a positive integer")?,
+        None => DEFAULT_PREAMBLE_LENGTH,
+    };
+
+    if env::args().nth(1) == Some(String::from("validate")) {
+        let stdin = io::stdin();
+        let mut validator = xmas::XmasValidator::new(preamble_length);
+        for (index, line) in stdin.lock().lines().enumerate() {
+            let line = line?;
+            let number = line.trim().parse::<i64>().map_err(|_| format!("index {}: \"{}\" is not a number", index, line))?;
+            if validator.push(number) == Some(false) {
+                println!("index {}: invalid XMAS number {}", index, number);
+            }
+        }
+        std::process::exit(0);
+    }
 
-fn main() -> Result<(), io::Error> {
     let input_path = Path::new("./input.txt");
     let input_file = File::open(input_path)?;
     let program: Vec<i64> = io::BufReader::new(input_file).lines().map(|l| l.unwrap().parse::<i64>().unwrap()).collect();
 
-    if env::args().skip(1).next() == Some(String::from("part1")) {
-        if let Some(invalid_item) = part1(25, &program) {
+    if env::args().nth(1) == Some(String::from("part1")) {
+        if let Some(invalid_item) = part1(preamble_length, &program) {
             println!("Invalid XMAS number {}", invalid_item);
         }
         std::process::exit(0);
-    } else if env::args().skip(1).next() == Some(String::from("part2")) {
-        if let Some(weakness) = part2(&program) {
+    } else if env::args().nth(1) == Some(String::from("part2")) {
+        if let Some((range, weakness)) = part2(preamble_length, &program) {
+            println!("Contiguous range {}..{} sums to the invalid number", range.start, range.end);
             println!("XMAS encryption weakness {}", weakness);
         }
         std::process::exit(0);
+    } else if env::args().nth(1) == Some(String::from("ranges")) {
+        if let Some((invalid_index, invalid_item)) = xmas::invalid_numbers(preamble_length, program.iter().copied()).next() {
+            for range in xmas::contiguous_ranges(&program[..invalid_index], invalid_item) {
+                println!("{}..{}", range.start, range.end);
+            }
+        }
+        std::process::exit(0);
     } else {
-        println!("usage: cargo run (part1 | part2)");
+        println!("usage: cargo run (part1 | part2 | validate | ranges) [preamble length]");
         std::process::exit(1);
     }
 }
 
-fn part1(preamble_length: usize, cipher_text: &Vec<i64>) -> Option<i64> {
-    let mut sliding_window = HashMap::<i64,i64>::new();
-    for i in 0..preamble_length {
-        sliding_window.insert(cipher_text[i], 1);
-    }
-    for (earliest_block, next_block) in cipher_text.iter().zip(cipher_text[preamble_length..].iter()) {
-        let mut found_two_sum = false;
-        for value in sliding_window.keys() {
-            let search_value = *next_block - *value;
-            if let Some(count) = sliding_window.get(&search_value) {
-                if (search_value == *value && *count >= 2) || *count >= 1 {
-                    found_two_sum = true;
-                    break;
-                }
-            }
-        }
-        if found_two_sum {
-            *sliding_window.get_mut(earliest_block).unwrap() -= 1;
-            *sliding_window.entry(*next_block).or_insert(0) += 1;
-        } else {
-            return Some(*next_block);
-        }
-    }
-    None
+fn part1(preamble_length: usize, cipher_text: &[i64]) -> Option<i64> {
+    xmas::invalid_numbers(preamble_length, cipher_text.iter().copied())
+        .map(|(_, number)| number)
+        .next()
 }
 
-fn part2(cipher_text: &Vec<i64>) -> Option<i64> {
-    if let Some(invalid_item) = part1(25, cipher_text) {
-        let sub_cipher = &cipher_text[..cipher_text.iter().position(|&a| a == invalid_item).unwrap()];
-        for i in 0..(sub_cipher.len()-2) {
-            for j in (i+2)..sub_cipher.len() {
-                let s = &sub_cipher[i..j];
-                match s.iter().sum::<i64>().cmp(&invalid_item) {
-                    Ordering::Less => continue,
-                    Ordering::Equal => return Some(*s.iter().min().unwrap() + *s.iter().max().unwrap()),
-                    Ordering::Greater => break,
-                }
-            }
-        }
-    }
-    None
+// The weakness comes from the numbers before the invalid one, returns the
+// range of indices along with the weakness.
+fn part2(preamble_length: usize, cipher_text: &[i64]) -> Option<(Range<usize>, i64)> {
+    let (invalid_index, invalid_item) = xmas::invalid_numbers(preamble_length, cipher_text.iter().copied()).next()?;
+    let range = xmas::contiguous_range(&cipher_text[..invalid_index], invalid_item)?;
+    let s = &cipher_text[range.clone()];
+    Some((range, *s.iter().min().unwrap() + *s.iter().max().unwrap()))
 }
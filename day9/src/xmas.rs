@@ -0,0 +1,222 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    ops::Range,
+};
+
+// The last `capacity` numbers pushed, with a count of each value so lookups
+// don't scan the window. Values are removed from the counts once their last
+// copy leaves the window.
+pub struct MultisetWindow {
+    capacity: usize,
+    order: VecDeque<i64>,
+    counts: HashMap<i64, usize>,
+}
+
+impl MultisetWindow {
+    pub fn new(capacity: usize) -> Self {
+        MultisetWindow {
+            capacity,
+            order: VecDeque::with_capacity(capacity + 1),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.order.len() == self.capacity
+    }
+
+    pub fn count(&self, value: i64) -> usize {
+        self.counts.get(&value).copied().unwrap_or(0)
+    }
+
+    // Returns the value that fell out of the window, if any.
+    pub fn push(&mut self, value: i64) -> Option<i64> {
+        self.order.push_back(value);
+        *self.counts.entry(value).or_insert(0) += 1;
+        if self.order.len() <= self.capacity {
+            return None;
+        }
+        let earliest = self.order.pop_front().unwrap();
+        let count = self.counts.get_mut(&earliest).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.counts.remove(&earliest);
+        }
+        Some(earliest)
+    }
+
+    // Whether two different entries in the window add up to `target`. A value
+    // only pairs with itself if it's in the window twice.
+    pub fn has_two_sum(&self, target: i64) -> bool {
+        self.counts.keys().any(|&value| {
+            let complement = target - value;
+            if complement == value {
+                self.count(value) >= 2
+            } else {
+                self.count(complement) >= 1
+            }
+        })
+    }
+}
+
+// Checks XMAS numbers one at a time against the previous `preamble_length`
+// numbers, so input can be validated as it arrives.
+pub struct XmasValidator {
+    window: MultisetWindow,
+}
+
+impl XmasValidator {
+    pub fn new(preamble_length: usize) -> Self {
+        XmasValidator {
+            window: MultisetWindow::new(preamble_length),
+        }
+    }
+
+    // Returns None while the preamble is still being read, otherwise whether
+    // `number` is the sum of two numbers in the window. Invalid numbers still
+    // join the window.
+    pub fn push(&mut self, number: i64) -> Option<bool> {
+        let valid = if self.window.is_full() {
+            Some(self.window.has_two_sum(number))
+        } else {
+            None
+        };
+        self.window.push(number);
+        valid
+    }
+}
+
+// Every number after the preamble that isn't a sum of two of the previous
+// `preamble_length` numbers, along with its index.
+pub fn invalid_numbers(
+    preamble_length: usize,
+    numbers: impl Iterator<Item = i64>,
+) -> impl Iterator<Item = (usize, i64)> {
+    let mut validator = XmasValidator::new(preamble_length);
+    numbers
+        .enumerate()
+        .filter(move |&(_, number)| validator.push(number) == Some(false))
+}
+
+// Prefix sums turn a contiguous range i..j into prefix[j] - prefix[i], so a
+// range summing to `target` is a pair of prefix sums `target` apart. This
+// works with negative numbers, unlike a sliding window. Ranges must contain
+// at least two numbers.
+fn prefix_sums(numbers: &[i64]) -> Vec<i64> {
+    let mut prefix = Vec::with_capacity(numbers.len() + 1);
+    prefix.push(0);
+    for number in numbers {
+        prefix.push(prefix.last().unwrap() + number);
+    }
+    prefix
+}
+
+// The range with the smallest end index, and the earliest start for that end.
+pub fn contiguous_range(numbers: &[i64], target: i64) -> Option<Range<usize>> {
+    let prefix = prefix_sums(numbers);
+    let mut earliest: HashMap<i64, usize> = HashMap::new();
+    for end in 2..prefix.len() {
+        earliest.entry(prefix[end - 2]).or_insert(end - 2);
+        if let Some(&start) = earliest.get(&(prefix[end] - target)) {
+            return Some(start..end);
+        }
+    }
+    None
+}
+
+// Every range summing to `target`, ordered by end then start.
+pub fn contiguous_ranges(numbers: &[i64], target: i64) -> Vec<Range<usize>> {
+    let prefix = prefix_sums(numbers);
+    let mut starts: HashMap<i64, Vec<usize>> = HashMap::new();
+    let mut ranges = Vec::new();
+    for end in 2..prefix.len() {
+        starts.entry(prefix[end - 2]).or_default().push(end - 2);
+        if let Some(matching) = starts.get(&(prefix[end] - target)) {
+            ranges.extend(matching.iter().map(|&start| start..end));
+        }
+    }
+    ranges
+}
+
+mod test {
+    const EXAMPLE: [i64; 20] = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
+
+    #[test]
+    fn multiset_window() {
+        use super::MultisetWindow;
+        let mut window = MultisetWindow::new(3);
+        assert_eq!(window.push(5), None);
+        assert_eq!(window.push(5), None);
+        assert!(window.has_two_sum(10));
+        assert_eq!(window.push(1), None);
+        assert!(window.is_full());
+        assert_eq!(window.push(2), Some(5));
+        assert!(!window.has_two_sum(10));
+        assert!(window.has_two_sum(6));
+        assert_eq!(window.push(3), Some(5));
+        assert_eq!(window.count(5), 0);
+        assert!(!window.has_two_sum(6));
+        assert_eq!(window.len(), 3);
+    }
+
+    #[test]
+    fn example_part1() {
+        use super::invalid_numbers;
+        let invalid: Vec<(usize, i64)> = invalid_numbers(5, EXAMPLE.iter().copied()).collect();
+        assert_eq!(invalid, vec![(14, 127)]);
+    }
+
+    #[test]
+    fn example_preamble_of_25() {
+        use super::XmasValidator;
+        // 1 to 25 in a random order, followed by 45.
+        let preamble = [
+            20, 3, 8, 14, 1, 22, 6, 11, 19, 2, 25, 17, 4, 13, 9, 23, 10, 16, 5, 12, 7, 24, 15, 18, 21,
+        ];
+        let check = |extra: &[i64], number| {
+            let mut validator = XmasValidator::new(25);
+            for &value in preamble.iter().chain(extra.iter()) {
+                validator.push(value);
+            }
+            validator.push(number).unwrap()
+        };
+        assert!(check(&[], 26));
+        assert!(check(&[], 49));
+        assert!(!check(&[], 100));
+        assert!(!check(&[], 50));
+        assert!(check(&[45], 26));
+        assert!(!check(&[45], 65));
+        assert!(check(&[45], 64));
+        assert!(check(&[45], 66));
+    }
+
+    #[test]
+    fn example_part2() {
+        use super::contiguous_range;
+        let range = contiguous_range(&EXAMPLE[..14], 127).unwrap();
+        assert_eq!(range, 2..6);
+        let s = &EXAMPLE[range];
+        assert_eq!(s.iter().min().unwrap() + s.iter().max().unwrap(), 62);
+    }
+
+    #[test]
+    fn contiguous_ranges_with_negatives() {
+        use super::{contiguous_range, contiguous_ranges};
+        let numbers = vec![3, -2, 5, 4, -4, 4, 1];
+        assert_eq!(contiguous_range(&numbers, 5), Some(2..5));
+        assert_eq!(contiguous_ranges(&numbers, 5), vec![2..5, 3..7, 5..7]);
+        assert_eq!(contiguous_range(&numbers, 100), None);
+        // A single number equal to the target doesn't count.
+        assert_eq!(contiguous_range(&[1, 9, 2], 9), None);
+    }
+}